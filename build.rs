@@ -0,0 +1,103 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// *********************************************************************
+/// With the embedded-assets feature, generate a table of every file in
+/// resources/ so they can be compiled into the executable. Only the
+/// unused/ folder at the top of resources/ is left out.
+/// *********************************************************************
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    write_lang_keys();
+
+    if env::var_os("CARGO_FEATURE_EMBEDDED_ASSETS").is_none() {
+        return;
+    }
+
+    let root = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("resources");
+    println!("cargo:rerun-if-changed={}", root.display());
+
+    let mut files = Vec::new();
+    list_files(&root, &mut files);
+    files.sort();
+
+    let mut table = String::from("pub static EMBEDDED: &[(&str, &[u8])] = &[\n");
+
+    for file in files {
+        let relative = file.strip_prefix(&root).unwrap();
+        let parts: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+
+        // Art that isn't used by the game doesn't need to be shipped
+        if parts.len() > 1 && parts[0] == "unused" {
+            continue;
+        }
+
+        println!("cargo:rerun-if-changed={}", file.display());
+        table.push_str(&format!("    ({:?}, include_bytes!({:?})),\n", format!("/{}", parts.join("/")), file));
+    }
+
+    table.push_str("];\n");
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("embedded.rs");
+    fs::write(out, table).expect("aieee, could not write the embedded asset table!");
+}
+
+fn list_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                println!("cargo:rerun-if-changed={}", path.display());
+                list_files(&path, files);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+}
+
+/// *********************************************************************
+/// Generate a table of every string the code looks up by key, so the
+/// validator can check the default language has all of them. Plural
+/// strings are marked, since they're given in more than one form.
+/// *********************************************************************
+
+fn write_lang_keys() {
+    let src = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("src");
+    println!("cargo:rerun-if-changed={}", src.display());
+
+    let mut files = Vec::new();
+    list_files(&src, &mut files);
+
+    let mut keys = BTreeSet::new();
+
+    for file in files {
+        let text = fs::read_to_string(&file).unwrap_or_default();
+
+        for (call, plural) in [(".tr(\"", false), (".tr_args(\"", false), (".tr_count(\"", true)] {
+            for (start, _) in text.match_indices(call) {
+                let rest = &text[start + call.len()..];
+
+                if let Some(end) = rest.find('"') {
+                    keys.insert((rest[..end].to_owned(), plural));
+                }
+            }
+        }
+    }
+
+    let mut table = String::from("pub static USED_KEYS: &[(&str, bool)] = &[\n");
+
+    for (key, plural) in keys {
+        table.push_str(&format!("    ({:?}, {}),\n", key, plural));
+    }
+
+    table.push_str("];\n");
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join("lang_keys.rs");
+    fs::write(out, table).expect("aieee, could not write the language key table!");
+}
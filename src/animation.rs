@@ -0,0 +1,188 @@
+use crate::sprite::*;
+
+/// *********************************************************************
+/// Create an enumeration of all the animation clips an entity can play.
+/// Each clip is looked up by name in the entity's sprite sheet.
+/// *********************************************************************
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Clip {
+    Idle,
+    Walk,
+    Run,
+    Jump,
+    Fall,
+    Attack,
+    Hurt,
+    Death,
+}
+
+pub const CLIPS: [Clip; 8] = [Clip::Idle, Clip::Walk, Clip::Run, Clip::Jump, Clip::Fall, Clip::Attack, Clip::Hurt, Clip::Death];
+
+impl Clip {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Clip::Idle => "idle",
+            Clip::Walk => "walk",
+            Clip::Run => "run",
+            Clip::Jump => "jump",
+            Clip::Fall => "fall",
+            Clip::Attack => "attack",
+            Clip::Hurt => "hurt",
+            Clip::Death => "death",
+        }
+    }
+
+    /// A one-shot clip that is still playing can only be interrupted by a
+    /// clip with a higher priority.
+    fn priority(&self) -> u8 {
+        match self {
+            Clip::Idle | Clip::Walk | Clip::Run => 0,
+            Clip::Jump | Clip::Fall => 1,
+            Clip::Attack => 2,
+            Clip::Hurt => 3,
+            Clip::Death => 4,
+        }
+    }
+}
+
+/// *********************************************************************
+/// Define a struct tracking which clip an entity is playing and how far
+/// through it the entity is.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct Animator {
+    pub clip: Clip,
+    pub frame: usize,
+    pub ticks: u32,
+    pub looping: bool,
+    pub finished: bool,
+}
+
+impl Animator {
+    pub fn new(clip: Clip) -> Animator {
+        Animator {clip, frame: 0, ticks: 0, looping: true, finished: false}
+    }
+
+    /// Ask to play a clip. Looping clips give way to anything, one-shot
+    /// clips only to higher priority clips until they finish, and death
+    /// never gives way.
+    pub fn request(&mut self, clip: Clip) {
+        let done = self.finished && ! self.looping;
+
+        if self.clip == Clip::Death || (clip == self.clip && ! done) {
+            return;
+        }
+
+        let locked = ! self.looping && ! self.finished && clip.priority() <= self.clip.priority();
+
+        if ! locked {
+            *self = Animator::new(clip);
+        }
+    }
+
+    /// Advance the clip by one tick using its timing from the sprite sheet.
+    pub fn update(&mut self, animation: Option<&Animation>) {
+        let animation = match animation {
+            Some(t) => t,
+            None => return,
+        };
+
+        self.looping = animation.looping;
+
+        if self.finished {
+            return;
+        }
+
+        self.ticks += 1;
+
+        if self.ticks >= animation.duration {
+            self.ticks = 0;
+
+            if self.frame + 1 < animation.frames.len() {
+                self.frame += 1;
+            } else if animation.looping {
+                self.frame = 0;
+            } else {
+                self.finished = true;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn animation(clip: Clip, frames: usize, looping: bool) -> Animation {
+        Animation {name: clip.name().to_owned(), frames: (0..frames).collect(), duration: 1, looping, flip: false}
+    }
+
+    /// Start playing a clip, learning whether it loops from its first tick.
+    fn start(anim: &mut Animator, clip: Clip, looping: bool) {
+        anim.request(clip);
+        anim.update(Some(&animation(clip, 3, looping)));
+    }
+
+    #[test]
+    fn lower_priority_cant_interrupt() {
+        let mut anim = Animator::new(Clip::Idle);
+        start(&mut anim, Clip::Hurt, false);
+
+        anim.request(Clip::Walk);
+        anim.request(Clip::Attack);
+        assert!(anim.clip == Clip::Hurt);
+
+        anim.request(Clip::Death);
+        assert!(anim.clip == Clip::Death);
+    }
+
+    #[test]
+    fn one_shot_gives_way_when_finished() {
+        let mut anim = Animator::new(Clip::Idle);
+        start(&mut anim, Clip::Attack, false);
+
+        for _ in 0..3 {
+            anim.update(Some(&animation(Clip::Attack, 3, false)));
+        }
+
+        assert!(anim.finished);
+        assert_eq!(anim.frame, 2);
+
+        anim.request(Clip::Walk);
+        assert!(anim.clip == Clip::Walk);
+    }
+
+    #[test]
+    fn looping_clip_wraps_and_gives_way() {
+        let mut anim = Animator::new(Clip::Walk);
+
+        for _ in 0..3 {
+            anim.update(Some(&animation(Clip::Walk, 3, true)));
+        }
+
+        assert_eq!(anim.frame, 0);
+        assert!(! anim.finished);
+
+        anim.request(Clip::Idle);
+        assert!(anim.clip == Clip::Idle);
+    }
+
+    #[test]
+    fn death_cant_be_left() {
+        let mut anim = Animator::new(Clip::Idle);
+        start(&mut anim, Clip::Death, false);
+
+        for _ in 0..5 {
+            anim.update(Some(&animation(Clip::Death, 3, false)));
+        }
+
+        assert!(anim.finished);
+
+        for clip in CLIPS {
+            anim.request(clip);
+            assert!(anim.clip == Clip::Death);
+        }
+    }
+}
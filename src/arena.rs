@@ -0,0 +1,229 @@
+use ggez::{Context, GameResult};
+
+use crate::assets::*;
+use crate::background::*;
+use crate::audio::*;
+use crate::data::*;
+use crate::entity::*;
+use crate::gui::*;
+use crate::lang::*;
+use crate::score::*;
+use crate::tilemap::*;
+
+pub const ARENAS_FILE: &str = "/arenas.cfg";
+
+/// *********************************************************************
+/// Define a struct describing an arena: the level it is played in, what
+/// it looks and sounds like, and where and which monsters appear.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct Arena {
+    pub id: String,
+    pub name: String,
+    pub level: String,
+    pub map: Tilemap,
+    pub layers: Vec<Layer>,
+    pub music: String,
+    pub start: (i16, i16),
+    pub spawns: Vec<(i16, i16)>,
+    pub monsters: Vec<EntityType>,
+}
+
+impl Arena {
+    /// Read an arena record, without loading its level.
+    fn from_record(record: &Record) -> GameResult<Arena> {
+        let mut spawns = Vec::new();
+
+        for value in record.all("spawn") {
+            spawns.push(parse_point(record, value)?);
+        }
+
+        let mut monsters = Vec::new();
+
+        for name in record.string("monsters")?.split_whitespace() {
+            match name {
+                "zombie" => monsters.push(EntityType::Zombie),
+                "skeleton" => monsters.push(EntityType::Skeleton),
+                _ => return Err(record.error(&format!("'{}' is not a monster", name))),
+            }
+        }
+
+        if monsters.is_empty() {
+            return Err(record.error("needs at least one monster"));
+        }
+
+        let start = match record.get("start") {
+            Some(t) => parse_point(record, t)?,
+            None => (152, GROUND as i16),
+        };
+
+        Ok (
+            Arena {
+                id: record.string("id")?,
+                name: record.string("name")?,
+                level: record.string("level")?,
+                map: Tilemap::flat(),
+                layers: Vec::new(),
+                music: record.get("music").unwrap_or(MUSIC_FILE).to_owned(),
+                start,
+                spawns,
+                monsters,
+            }
+        )
+    }
+
+    /// The arena used when arenas.cfg can't be loaded: the flat floor the
+    /// game started out with.
+    pub fn fallback() -> Arena {
+        Arena {
+            id: "fallback".to_owned(),
+            name: "fallback".to_owned(),
+            level: String::new(),
+            map: Tilemap::flat(),
+            layers: Vec::new(),
+            music: MUSIC_FILE.to_owned(),
+            start: (152, GROUND as i16),
+            spawns: Vec::new(),
+            monsters: vec![EntityType::Zombie, EntityType::Skeleton],
+        }
+    }
+
+    /// The image of every background layer.
+    pub fn images(&self) -> Vec<String> {
+        self.layers.iter().map(|l| l.image.clone()).collect()
+    }
+}
+
+/// Parse a position written as 'x y', in pixels.
+fn parse_point(record: &Record, value: &str) -> GameResult<(i16, i16)> {
+    let parts: Vec<Option<i16>> = value.split_whitespace().map(|t| t.parse::<i16>().ok()).collect();
+
+    match parts.as_slice() {
+        [Some(x), Some(y)] => Ok((*x, *y)),
+        _ => Err(record.error(&format!("'{}' is not a position like '152 216'", value))),
+    }
+}
+
+/// *********************************************************************
+/// Read every [arena] record in an arenas file, along with the [layer]
+/// records after each one. Levels still have to be loaded separately.
+/// *********************************************************************
+
+pub fn arenas_from_records(records: &[Record]) -> GameResult<Vec<Arena>> {
+    let mut list: Vec<Arena> = Vec::new();
+
+    for record in records {
+        match record.kind.as_str() {
+            "arena" => {
+                let arena = Arena::from_record(record)?;
+
+                if list.iter().any(|a| a.id == arena.id) {
+                    return Err(record.error(&format!("arena '{}' is defined twice", arena.id)));
+                }
+
+                list.push(arena);
+            }
+
+            "layer" => match list.last_mut() {
+                Some(arena) => arena.layers.push(Layer::from_record(record)?),
+                None => return Err(record.error("comes before any [arena]")),
+            },
+
+            _ => return Err(record.error("unknown record type")),
+        }
+    }
+
+    if list.is_empty() {
+        return Err(ggez::GameError::ResourceLoadError(format!("{}: no [arena] records", ARENAS_FILE)));
+    }
+
+    Ok(list)
+}
+
+/// *********************************************************************
+/// Define a struct holding every arena, the one selected in the arena
+/// select screen, and the best score in each.
+/// *********************************************************************
+
+pub struct Arenas {
+    pub list: Vec<Arena>,
+    pub selected: usize,
+    pub scores: HighScores,
+}
+
+impl Arenas {
+    /// Load every arena and its level. Anything that fails to load is added
+    /// to the report; a broken arenas file leaves just the fallback arena
+    /// and a broken level leaves its arena with a flat floor.
+    pub fn new(ctx: &mut Context, report: &mut LoadReport) -> Arenas {
+        let mut list = match load_records(ctx, ARENAS_FILE).and_then(|r| arenas_from_records(&r)) {
+            Ok(t) => t,
+            Err(e) => {
+                report.add(ARENAS_FILE, &e);
+                vec![Arena::fallback()]
+            }
+        };
+
+        for arena in list.iter_mut().filter(|a| ! a.level.is_empty()) {
+            arena.map = Tilemap::new_or_default(ctx, &arena.level, report);
+        }
+
+        Arenas {list, selected: 0, scores: HighScores::load()}
+    }
+
+    pub fn current(&self) -> &Arena {
+        &self.list[self.selected]
+    }
+
+    /// Reload arenas.cfg and any changed levels, keeping the selection where
+    /// possible.
+    pub fn reload(&mut self, ctx: &mut Context, changed: &[String]) -> GameResult {
+        if changed.iter().any(|c| c == ARENAS_FILE) {
+            let mut list = arenas_from_records(&load_records(ctx, ARENAS_FILE)?)?;
+
+            for arena in list.iter_mut() {
+                arena.map = Tilemap::new(ctx, &arena.level)?;
+            }
+
+            let id = self.current().id.clone();
+            self.selected = list.iter().position(|a| a.id == id).unwrap_or(0);
+            self.list = list;
+
+            return Ok(());
+        }
+
+        for arena in self.list.iter_mut() {
+            if changed.contains(&arena.level) {
+                arena.map = Tilemap::new(ctx, &arena.level)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// *********************************************************************
+/// Create a function to build the arena select menu, listing each arena
+/// with its best score.
+/// *********************************************************************
+
+pub fn arenas_menu(arenas: &Arenas, lang: &Lang) -> Menu {
+    let items = arenas.list.iter().map(|arena| arena.name.clone()).collect();
+    let best = arenas.list.iter().map(|arena| lang.tr_args("arena_best", &[("score", &arenas.scores.best(&arena.id))])).collect();
+
+    let buttons = Widget::stack(Axis::Horizontal, Align::Start, 16.0, vec![
+        Widget::text_button("play", lang.tr("play")),
+        Widget::text_button("back", lang.tr("back")),
+    ]);
+
+    let root = Widget::stack(Axis::Vertical, Align::Start, 12.0, vec![
+        Widget::title(lang.tr("choose_arena")),
+        Widget::list("arenas", items, arenas.selected).values(best),
+        buttons,
+    ]);
+
+    let help = Widget::label(lang.tr("arenas_help"));
+
+    Menu::new(root, (Align::Start, Align::Start)).panel(help, (Align::Start, Align::End)).with_focus("arenas")
+}
@@ -1,238 +1,257 @@
-use ggez::graphics::{self, FilterMode};
+use ggez::graphics::{self, FilterMode, Rect};
 
-use ggez::{Context, GameResult};
+use ggez::{Context, GameError, GameResult};
 
+use native_dialog::{MessageDialog, MessageType};
+
+use crate::animation::*;
 use crate::entity::*;
+use crate::files::*;
+use crate::sprite::*;
+
+pub const PLAYER_SHEET: &str = "/sprites/player.sheet";
+pub const ZOMBIE_SHEET: &str = "/sprites/zombie.sheet";
+pub const SKELETON_SHEET: &str = "/sprites/skeleton.sheet";
+pub const GROUND_IMAGE: &str = "/ground.png";
+pub const GRASS_IMAGE: &str = "/grass.png";
+pub const MOSS_IMAGE: &str = "/moss.png";
+pub const PLAY_IMAGE: &str = "/gui/play.png";
+pub const FONT: &str = "/MorePerfectDOSVGA.ttf";
+
+/// The images a level's tiles can be drawn with.
+pub const TILE_IMAGES: [&str; 3] = [GROUND_IMAGE, GRASS_IMAGE, MOSS_IMAGE];
+
+/// *********************************************************************
+/// Define a struct collecting every file that failed to load, so they
+/// can all be reported at once instead of stopping at the first.
+/// *********************************************************************
+
+#[derive(Default)]
+pub struct LoadReport {
+    pub failures: Vec<String>,
+}
+
+impl LoadReport {
+    pub fn add(&mut self, path: &str, error: &GameError) {
+        self.failures.push(format!("{}: {}", path, error));
+    }
+
+    /// Show every failure in a single dialog, if there were any.
+    pub fn show(&self) {
+        if self.failures.is_empty() {
+            return;
+        }
+
+        let text = format!("Some game files are missing or broken, so placeholders are being used instead:\n\n{}", self.failures.join("\n"));
+        let _ = MessageDialog::new().set_title("game").set_text(&text).set_type(MessageType::Warning).show_alert();
+        eprintln!("{}", text);
+    }
+}
 
 /// *********************************************************************
 /// Create a struct containing all the assets used by the game.
 /// *********************************************************************
 
 pub struct Assets {
-    pub player: graphics::Image,
-    pub player2: graphics::Image,
-    pub player3: graphics::Image,
-    pub player4: graphics::Image,
-    pub player5: graphics::Image,
-    pub player6: graphics::Image,
-    pub player7: graphics::Image,
-    pub player8: graphics::Image,
-    pub player9: graphics::Image,
-    pub player0: graphics::Image,
-    pub zombie: graphics::Image,
-    pub zombie2: graphics::Image,
-    pub zombie3: graphics::Image,
-    pub zombie4: graphics::Image,
-    pub zombie5: graphics::Image,
-    pub zombie6: graphics::Image,
-    pub zombie7: graphics::Image,
-    pub zombie8: graphics::Image,
-    pub zombie9: graphics::Image,
-    pub zombie0: graphics::Image,
-    pub skeleton: graphics::Image,
-    pub skeleton2: graphics::Image,
-    pub skeleton3: graphics::Image,
-    pub skeleton4: graphics::Image,
-    pub skeleton5: graphics::Image,
-    pub skeleton6: graphics::Image,
-    pub skeleton7: graphics::Image,
-    pub skeleton8: graphics::Image,
-    pub skeleton9: graphics::Image,
-    pub skeleton0: graphics::Image,
+    pub player: SpriteSheet,
+    pub zombie: SpriteSheet,
+    pub skeleton: SpriteSheet,
     pub ground: graphics::Image,
     pub grass: graphics::Image,
     pub moss: graphics::Image,
     pub play: graphics::Image,
     pub font: graphics::Font,
+    pub backgrounds: Vec<(String, graphics::Image)>,
 }
 
 impl Assets {
-    pub fn new(ctx: &mut Context) -> GameResult<Assets> {
-        let mut player = graphics::Image::new(ctx, "/player.png")?;
-        let mut player2 = graphics::Image::new(ctx, "/player2.png")?;
-        let mut player3 = graphics::Image::new(ctx, "/player3.png")?;
-        let mut player4 = graphics::Image::new(ctx, "/player4.png")?;
-        let mut player5 = graphics::Image::new(ctx, "/player5.png")?;
-        let mut player6 = graphics::Image::new(ctx, "/player6.png")?;
-        let mut player7 = graphics::Image::new(ctx, "/player7.png")?;
-        let mut player8 = graphics::Image::new(ctx, "/player8.png")?;
-        let mut player9 = graphics::Image::new(ctx, "/player9.png")?;
-        let mut player0 = graphics::Image::new(ctx, "/player0.png")?;
-        let mut zombie = graphics::Image::new(ctx, "/zombie.png")?;
-        let mut zombie2 = graphics::Image::new(ctx, "/zombie2.png")?;
-        let mut zombie3 = graphics::Image::new(ctx, "/zombie3.png")?;
-        let mut zombie4 = graphics::Image::new(ctx, "/zombie4.png")?;
-        let mut zombie5 = graphics::Image::new(ctx, "/zombie5.png")?;
-        let mut zombie6 = graphics::Image::new(ctx, "/zombie6.png")?;
-        let mut zombie7 = graphics::Image::new(ctx, "/zombie7.png")?;
-        let mut zombie8 = graphics::Image::new(ctx, "/zombie8.png")?;
-        let mut zombie9 = graphics::Image::new(ctx, "/zombie9.png")?;
-        let mut zombie0 = graphics::Image::new(ctx, "/zombie0.png")?;
-        let mut skeleton = graphics::Image::new(ctx, "/skeleton.png")?;
-        let mut skeleton2 = graphics::Image::new(ctx, "/skeleton2.png")?;
-        let mut skeleton3 = graphics::Image::new(ctx, "/skeleton3.png")?;
-        let mut skeleton4 = graphics::Image::new(ctx, "/skeleton4.png")?;
-        let mut skeleton5 = graphics::Image::new(ctx, "/skeleton5.png")?;
-        let mut skeleton6 = graphics::Image::new(ctx, "/skeleton6.png")?;
-        let mut skeleton7 = graphics::Image::new(ctx, "/skeleton7.png")?;
-        let mut skeleton8 = graphics::Image::new(ctx, "/skeleton8.png")?;
-        let mut skeleton9 = graphics::Image::new(ctx, "/skeleton9.png")?;
-        let mut skeleton0 = graphics::Image::new(ctx, "/skeleton0.png")?;
-        let mut ground = graphics::Image::new(ctx, "/ground.png")?;
-        let mut grass = graphics::Image::new(ctx, "/grass.png")?;
-        let mut moss = graphics::Image::new(ctx, "/moss.png")?;
-        let mut play = graphics::Image::new(ctx, "/gui/play.png")?;
-        let font = graphics::Font::new(ctx, "/MorePerfectDOSVGA.ttf")?;
-
-        player.set_filter(FilterMode::Nearest);
-        player2.set_filter(FilterMode::Nearest);
-        player3.set_filter(FilterMode::Nearest);
-        player4.set_filter(FilterMode::Nearest);
-        player5.set_filter(FilterMode::Nearest);
-        player6.set_filter(FilterMode::Nearest);
-        player7.set_filter(FilterMode::Nearest);
-        player8.set_filter(FilterMode::Nearest);
-        player9.set_filter(FilterMode::Nearest);
-        player0.set_filter(FilterMode::Nearest);
-        zombie.set_filter(FilterMode::Nearest);
-        zombie2.set_filter(FilterMode::Nearest);
-        zombie3.set_filter(FilterMode::Nearest);
-        zombie4.set_filter(FilterMode::Nearest);
-        zombie5.set_filter(FilterMode::Nearest);
-        zombie6.set_filter(FilterMode::Nearest);
-        zombie7.set_filter(FilterMode::Nearest);
-        zombie8.set_filter(FilterMode::Nearest);
-        zombie9.set_filter(FilterMode::Nearest);
-        zombie0.set_filter(FilterMode::Nearest);
-        skeleton.set_filter(FilterMode::Nearest);
-        skeleton2.set_filter(FilterMode::Nearest);
-        skeleton3.set_filter(FilterMode::Nearest);
-        skeleton4.set_filter(FilterMode::Nearest);
-        skeleton5.set_filter(FilterMode::Nearest);
-        skeleton6.set_filter(FilterMode::Nearest);
-        skeleton7.set_filter(FilterMode::Nearest);
-        skeleton8.set_filter(FilterMode::Nearest);
-        skeleton9.set_filter(FilterMode::Nearest);
-        skeleton0.set_filter(FilterMode::Nearest);
-        ground.set_filter(FilterMode::Nearest);
-        grass.set_filter(FilterMode::Nearest);
-        moss.set_filter(FilterMode::Nearest);
-        play.set_filter(FilterMode::Nearest);
+    /// Load every asset. Anything that fails to load is added to the
+    /// report and replaced by a checkerboard placeholder, or the default
+    /// font for the font.
+    pub fn new(ctx: &mut Context, report: &mut LoadReport) -> GameResult<Assets> {
+        let font = match load_font(ctx) {
+            Ok(t) => t,
+            Err(e) => {
+                report.add(FONT, &e);
+                graphics::Font::default()
+            }
+        };
 
         Ok (
             Assets {
-                player,
-                player2,
-                player3,
-                player4,
-                player5,
-                player6,
-                player7,
-                player8,
-                player9,
-                player0,
-                zombie,
-                zombie2,
-                zombie3,
-                zombie4,
-                zombie5,
-                zombie6,
-                zombie7,
-                zombie8,
-                zombie9,
-                zombie0,
-                skeleton,
-                skeleton2,
-                skeleton3,
-                skeleton4,
-                skeleton5,
-                skeleton6,
-                skeleton7,
-                skeleton8,
-                skeleton9,
-                skeleton0,
-                ground,
-                grass,
-                moss,
-                play,
+                player: load_sheet_or_placeholder(ctx, PLAYER_SHEET, report)?,
+                zombie: load_sheet_or_placeholder(ctx, ZOMBIE_SHEET, report)?,
+                skeleton: load_sheet_or_placeholder(ctx, SKELETON_SHEET, report)?,
+                ground: load_image_or_placeholder(ctx, GROUND_IMAGE, report)?,
+                grass: load_image_or_placeholder(ctx, GRASS_IMAGE, report)?,
+                moss: load_image_or_placeholder(ctx, MOSS_IMAGE, report)?,
+                play: load_image_or_placeholder(ctx, PLAY_IMAGE, report)?,
                 font,
+                backgrounds: Vec::new(),
             }
         )
     }
 
-    pub fn image(&mut self, entity: &Entity) -> &mut graphics::Image {
-        match entity.tag {
-            EntityType::Player => {
-                match entity.facing {
-                    Direction::Left => {
-                        match entity.frame {
-                            Frame::Stand => &mut self.player,
-                            Frame::Walk1 => &mut self.player3,
-                            Frame::Walk2 => &mut self.player5,
-                            Frame::Walk3 => &mut self.player7,
-                            Frame::Walk4 => &mut self.player9,
-                        }
-                    }
-
-                    Direction::Right => {
-                        match entity.frame {
-                            Frame::Stand => &mut self.player2,
-                            Frame::Walk1 => &mut self.player4,
-                            Frame::Walk2 => &mut self.player6,
-                            Frame::Walk3 => &mut self.player8,
-                            Frame::Walk4 => &mut self.player0,
-                        }
-                    }
+    /// Reload every asset that uses one of the `changed` resource files.
+    /// An asset that fails to reload keeps its old version, and the first
+    /// error is returned once everything else has been tried.
+    pub fn reload(&mut self, ctx: &mut Context, changed: &[String]) -> GameResult {
+        let mut result = Ok(());
+
+        for sheet in [&mut self.player, &mut self.zombie, &mut self.skeleton] {
+            if changed.contains(&sheet.path) || changed.contains(&sheet.def.image) {
+                match load_sheet(ctx, &sheet.path.clone()) {
+                    Ok(t) => *sheet = t,
+                    Err(e) => result = result.and(Err(e)),
                 }
             }
+        }
 
-            EntityType::Zombie => {
-                match entity.facing {
-                    Direction::Left => {
-                        match entity.frame {
-                            Frame::Stand => &mut self.zombie,
-                            Frame::Walk1 => &mut self.zombie3,
-                            Frame::Walk2 => &mut self.zombie5,
-                            Frame::Walk3 => &mut self.zombie7,
-                            Frame::Walk4 => &mut self.zombie9,
-                        }
-                    }
-
-                    Direction::Right => {
-                        match entity.frame {
-                            Frame::Stand => &mut self.zombie2,
-                            Frame::Walk1 => &mut self.zombie4,
-                            Frame::Walk2 => &mut self.zombie6,
-                            Frame::Walk3 => &mut self.zombie8,
-                            Frame::Walk4 => &mut self.zombie0,
-                        }
-                    }
+        for (image, path) in [(&mut self.ground, GROUND_IMAGE), (&mut self.grass, GRASS_IMAGE), (&mut self.moss, MOSS_IMAGE), (&mut self.play, PLAY_IMAGE)] {
+            if changed.iter().any(|c| c == path) {
+                match load_image(ctx, path) {
+                    Ok(t) => *image = t,
+                    Err(e) => result = result.and(Err(e)),
                 }
             }
+        }
 
-            EntityType::Skeleton => {
-                match entity.facing {
-                    Direction::Left => {
-                        match entity.frame {
-                            Frame::Stand => &mut self.skeleton,
-                            Frame::Walk1 => &mut self.skeleton3,
-                            Frame::Walk2 => &mut self.skeleton5,
-                            Frame::Walk3 => &mut self.skeleton7,
-                            Frame::Walk4 => &mut self.skeleton9,
-                        }
-                    }
-
-                    Direction::Right => {
-                        match entity.frame {
-                            Frame::Stand => &mut self.skeleton2,
-                            Frame::Walk1 => &mut self.skeleton4,
-                            Frame::Walk2 => &mut self.skeleton6,
-                            Frame::Walk3 => &mut self.skeleton8,
-                            Frame::Walk4 => &mut self.skeleton0,
-                        }
-                    }
+        for (path, image) in self.backgrounds.iter_mut() {
+            if changed.contains(path) {
+                match load_image(ctx, path) {
+                    Ok(t) => *image = t,
+                    Err(e) => result = result.and(Err(e)),
                 }
             }
         }
+
+        if changed.iter().any(|c| c == FONT) {
+            match load_font(ctx) {
+                Ok(t) => self.font = t,
+                Err(e) => result = result.and(Err(e)),
+            }
+        }
+
+        result
+    }
+
+    /// Load the background layer images used by the arenas.
+    pub fn load_backgrounds(&mut self, ctx: &mut Context, paths: &[String], report: &mut LoadReport) -> GameResult {
+        for path in paths {
+            if ! self.backgrounds.iter().any(|(p, _)| p == path) {
+                let image = load_image_or_placeholder(ctx, path, report)?;
+                self.backgrounds.push((path.clone(), image));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn background(&self, path: &str) -> Option<&graphics::Image> {
+        self.backgrounds.iter().find(|(p, _)| p == path).map(|(_, image)| image)
+    }
+
+    pub fn sheet(&self, tag: &EntityType) -> &SpriteSheet {
+        match tag {
+            EntityType::Player => &self.player,
+            EntityType::Zombie => &self.zombie,
+            EntityType::Skeleton => &self.skeleton,
+        }
+    }
+}
+
+fn load_font(ctx: &mut Context) -> GameResult<graphics::Font> {
+    let bytes = read_file(ctx, FONT)?;
+    graphics::Font::new_glyph_font_bytes(ctx, &bytes)
+}
+
+/// *********************************************************************
+/// Load an image for pixel art.
+/// *********************************************************************
+
+fn load_image(ctx: &mut Context, path: &str) -> GameResult<graphics::Image> {
+    let bytes = read_file(ctx, path)?;
+    let mut image = graphics::Image::from_bytes(ctx, &bytes)?;
+    image.set_filter(FilterMode::Nearest);
+    Ok(image)
+}
+
+fn load_image_or_placeholder(ctx: &mut Context, path: &str, report: &mut LoadReport) -> GameResult<graphics::Image> {
+    match load_image(ctx, path) {
+        Ok(t) => Ok(t),
+        Err(e) => {
+            report.add(path, &e);
+            placeholder_image(ctx)
+        }
+    }
+}
+
+/// *********************************************************************
+/// Create a magenta and black checkerboard that is easy to spot in game.
+/// *********************************************************************
+
+fn placeholder_image(ctx: &mut Context) -> GameResult<graphics::Image> {
+    let mut rgba = Vec::new();
+
+    for y in 0..16 {
+        for x in 0..16 {
+            if (x / 4 + y / 4) % 2 == 0 {
+                rgba.extend_from_slice(&[255, 0, 255, 255]);
+            } else {
+                rgba.extend_from_slice(&[0, 0, 0, 255]);
+            }
+        }
+    }
+
+    let mut image = graphics::Image::from_rgba8(ctx, 16, 16, &rgba)?;
+    image.set_filter(FilterMode::Nearest);
+    Ok(image)
+}
+
+/// *********************************************************************
+/// Load a character's sprite sheet and make sure it has every animation
+/// the game needs.
+/// *********************************************************************
+
+fn load_sheet(ctx: &mut Context, path: &str) -> GameResult<SpriteSheet> {
+    let sheet = SpriteSheet::new(ctx, path)?;
+
+    for clip in CLIPS {
+        if sheet.def.animation(clip.name()).is_none() {
+            return Err(GameError::ResourceLoadError(format!("{}: missing animation '{}'", path, clip.name())));
+        }
+    }
+
+    Ok(sheet)
+}
+
+/// *********************************************************************
+/// Load a sprite sheet, or make a sheet that plays the placeholder image
+/// for every animation if it can't be loaded.
+/// *********************************************************************
+
+fn load_sheet_or_placeholder(ctx: &mut Context, path: &str, report: &mut LoadReport) -> GameResult<SpriteSheet> {
+    match load_sheet(ctx, path) {
+        Ok(t) => Ok(t),
+        Err(e) => {
+            report.add(path, &e);
+
+            let frame = SheetFrame {name: "placeholder".to_owned(), rect: Rect::new(0.0, 0.0, 16.0, 16.0), pivot: 8.0};
+            let animations = CLIPS.iter().map(|clip| Animation {
+                name: clip.name().to_owned(),
+                frames: vec![0],
+                duration: 1,
+                looping: true,
+                flip: false,
+            }).collect();
+
+            Ok (
+                SpriteSheet {
+                    path: path.to_owned(),
+                    def: SheetDef {image: String::new(), frames: vec![frame], animations},
+                    image: placeholder_image(ctx)?,
+                }
+            )
+        }
     }
 }
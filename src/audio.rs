@@ -0,0 +1,277 @@
+use ggez::audio::{self, SoundSource};
+use ggez::{Context, GameError, GameResult};
+
+use crate::assets::*;
+use crate::events::*;
+use crate::files::*;
+use crate::gui::*;
+use crate::lang::*;
+use crate::settings::*;
+
+/// *********************************************************************
+/// Create an enumeration of all the sound effects.
+/// *********************************************************************
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sfx {
+    Jump,
+    Stomp,
+    Hurt,
+    Death,
+    Hover,
+    Click,
+    Spawn,
+}
+
+pub const SFX_FILES: [(Sfx, &str); 7] = [
+    (Sfx::Jump, "/sounds/jump.wav"),
+    (Sfx::Stomp, "/sounds/stomp.wav"),
+    (Sfx::Hurt, "/sounds/hurt.wav"),
+    (Sfx::Death, "/sounds/death.wav"),
+    (Sfx::Hover, "/sounds/hover.wav"),
+    (Sfx::Click, "/sounds/click.wav"),
+    (Sfx::Spawn, "/sounds/spawn.wav"),
+];
+
+/// The music played when an arena doesn't choose a track.
+pub const MUSIC_FILE: &str = "/music/theme.wav";
+
+/// *********************************************************************
+/// Define a struct holding the volume settings.
+/// *********************************************************************
+
+pub struct Volume {
+    pub master: f32,
+    pub music: f32,
+    pub sfx: f32,
+}
+
+impl Volume {
+    pub fn from_settings() -> Volume {
+        Volume {
+            master: fetch_volume("master_volume"),
+            music: fetch_volume("music_volume"),
+            sfx: fetch_volume("sfx_volume"),
+        }
+    }
+
+    /// Write the volumes to settings.cfg, so they're kept next time.
+    pub fn save(&self) -> std::io::Result<()> {
+        save_setting("master_volume", &self.master.to_string())?;
+        save_setting("music_volume", &self.music.to_string())?;
+        save_setting("sfx_volume", &self.sfx.to_string())
+    }
+}
+
+fn fetch_volume(setting: &str) -> f32 {
+    match fetch_setting_or(setting, SType::Float(1.0)) {
+        SType::Float(t) => t.clamp(0.0, 1.0),
+        _ => 1.0,
+    }
+}
+
+/// *********************************************************************
+/// The device backend plays sounds through ggez, skipping any sound
+/// whose file could not be loaded. The null backend throws them all
+/// away, so the game can run without an audio device.
+/// *********************************************************************
+
+enum Backend {
+    Null,
+    Device {
+        sfx: Vec<(Sfx, audio::Source)>,
+        music: Vec<(String, audio::Source)>,
+        playing: Option<String>,
+    },
+}
+
+/// *********************************************************************
+/// Define a struct to manage music and sound effects. Sounds requested
+/// by the simulation are queued and played by `flush`, so the
+/// simulation never needs a Context.
+/// *********************************************************************
+
+pub struct Audio {
+    backend: Backend,
+    queue: Vec<Sfx>,
+    pub volume: Volume,
+    pub muted: bool,
+}
+
+impl Audio {
+    /// Load every sound effect and every music track in `tracks`.
+    pub fn new(ctx: &mut Context, volume: Volume, muted: bool, tracks: &[String], report: &mut LoadReport) -> Audio {
+        let backend = match load_backend(ctx, tracks, report) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("Could not start audio, continuing without sound: {}.", e);
+                Backend::Null
+            }
+        };
+
+        let mut audio = Audio {backend, queue: Vec::new(), volume, muted};
+        audio.apply_volume();
+        audio
+    }
+
+    /// Queue a sound effect to be played on the next flush.
+    pub fn play(&mut self, sfx: Sfx) {
+        if ! self.muted {
+            self.queue.push(sfx);
+        }
+    }
+
+    /// Queue the sound effect for something that happened in the world.
+    pub fn handle(&mut self, event: &Event) {
+        match event {
+            Event::MonsterSpawned {..} => self.play(Sfx::Spawn),
+            Event::MonsterStomped {..} => self.play(Sfx::Stomp),
+            Event::PlayerJumped => self.play(Sfx::Jump),
+            Event::PlayerDamaged {..} => self.play(Sfx::Hurt),
+            Event::PlayerDied {..} => self.play(Sfx::Death),
+            _ => (),
+        }
+    }
+
+    /// Play every queued sound effect.
+    pub fn flush(&mut self, ctx: &mut Context) -> GameResult {
+        let queue = std::mem::take(&mut self.queue);
+
+        if let Backend::Device {sfx, ..} = &mut self.backend {
+            for wanted in queue {
+                if let Some((_, source)) = sfx.iter_mut().find(|(s, _)| *s == wanted) {
+                    source.play_detached(ctx)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Switch to a music track, unless it's already playing.
+    pub fn play_music(&mut self, ctx: &mut Context, track: &str) -> GameResult {
+        if let Backend::Device {music, playing, ..} = &mut self.backend {
+            if playing.as_deref() == Some(track) {
+                return Ok(());
+            }
+
+            for (path, source) in music.iter_mut() {
+                if path == track {
+                    source.play(ctx)?;
+                } else if source.playing() {
+                    source.stop(ctx)?;
+                }
+            }
+
+            *playing = Some(track.to_owned());
+        }
+
+        Ok(())
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.set_muted(! self.muted);
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+        self.queue.clear();
+        self.apply_volume();
+    }
+
+    pub fn set_volume(&mut self, volume: Volume) {
+        self.volume = volume;
+        self.apply_volume();
+    }
+
+    fn apply_volume(&mut self) {
+        let master = if self.muted { 0.0 } else { self.volume.master };
+
+        if let Backend::Device {sfx, music, ..} = &mut self.backend {
+            for (_, source) in sfx.iter_mut() {
+                source.set_volume(master * self.volume.sfx);
+            }
+
+            for (_, source) in music.iter_mut() {
+                source.set_volume(master * self.volume.music);
+            }
+        }
+    }
+}
+
+/// *********************************************************************
+/// Create a function to build the sound menu, with a slider for each
+/// volume and a toggle for muting.
+/// *********************************************************************
+
+pub fn sound_menu(audio: &Audio, lang: &Lang) -> Menu {
+    let volumes = Widget::stack(Axis::Vertical, Align::Start, 4.0, vec![
+        Widget::slider("master", lang.tr("master_volume"), audio.volume.master),
+        Widget::slider("music", lang.tr("music_volume"), audio.volume.music),
+        Widget::slider("sfx", lang.tr("sfx_volume"), audio.volume.sfx),
+    ]).padding(4.0);
+
+    let buttons = Widget::stack(Axis::Horizontal, Align::Start, 16.0, vec![
+        Widget::toggle("mute", lang.tr("mute"), audio.muted),
+        Widget::text_button("back", lang.tr("back")),
+    ]);
+
+    let root = Widget::stack(Axis::Vertical, Align::Start, 12.0, vec![
+        Widget::title(lang.tr("sound")),
+        volumes,
+        buttons,
+    ]);
+
+    let help = Widget::label(lang.tr("sound_help"));
+
+    Menu::new(root, (Align::Start, Align::Start)).panel(help, (Align::Start, Align::End)).with_focus("master")
+}
+
+/// *********************************************************************
+/// Load every sound. Missing or broken files go in the load report, but
+/// having no audio device at all gives up on sound entirely.
+/// *********************************************************************
+
+fn load_backend(ctx: &mut Context, tracks: &[String], report: &mut LoadReport) -> GameResult<Backend> {
+    let mut sfx = Vec::new();
+
+    for (effect, path) in SFX_FILES {
+        if let Some(source) = load_source(ctx, path, report)? {
+            sfx.push((effect, source));
+        }
+    }
+
+    let mut music: Vec<(String, audio::Source)> = Vec::new();
+
+    for track in tracks {
+        if music.iter().any(|(path, _)| path == track) {
+            continue;
+        }
+
+        if let Some(mut source) = load_source(ctx, track, report)? {
+            source.set_repeat(true);
+            music.push((track.clone(), source));
+        }
+    }
+
+    Ok(Backend::Device {sfx, music, playing: None})
+}
+
+fn load_source(ctx: &mut Context, path: &str, report: &mut LoadReport) -> GameResult<Option<audio::Source>> {
+    let data = match read_file(ctx, path) {
+        Ok(t) => audio::SoundData::from_bytes(&t),
+        Err(e) => {
+            report.add(path, &e);
+            return Ok(None);
+        }
+    };
+
+    // A file that can't be decoded is only a problem for that file
+    if ! data.can_play() {
+        report.add(path, &GameError::ResourceLoadError("not a sound file that can be played".to_owned()));
+        return Ok(None);
+    }
+
+    // Anything else going wrong means there's no audio device to play on
+    audio::Source::from_data(ctx, data).map(Some)
+}
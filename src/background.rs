@@ -0,0 +1,148 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, spritebatch::SpriteBatch, Color, DrawParam};
+use ggez::mint::Point2;
+
+use crate::assets::*;
+use crate::data::*;
+use crate::entity::*;
+use crate::render::*;
+
+/// How opaque layers are drawn unless they say otherwise, so the game
+/// stays readable in front of them.
+const DEFAULT_ALPHA: f32 = 0.35;
+
+/// *********************************************************************
+/// Create an enumeration of the directions a layer repeats in.
+/// *********************************************************************
+
+#[derive(Clone, PartialEq)]
+pub enum Tiling {
+    None,
+    X,
+    Y,
+    Both,
+}
+
+/// *********************************************************************
+/// Define a struct for one background layer. Layers with a smaller
+/// scroll factor move less as the camera moves, so they look further
+/// away.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct Layer {
+    pub image: String,
+    pub scroll: f32,
+    pub offset: f32,
+    pub tiling: Tiling,
+    pub alpha: f32,
+}
+
+impl Layer {
+    pub fn from_record(record: &Record) -> GameResult<Layer> {
+        let tiling = match record.get("tile").unwrap_or("x") {
+            "none" => Tiling::None,
+            "x" => Tiling::X,
+            "y" => Tiling::Y,
+            "both" => Tiling::Both,
+            other => return Err(record.error(&format!("'{}' is not none, x, y or both", other))),
+        };
+
+        let alpha = record.float_or("alpha", DEFAULT_ALPHA)?;
+
+        if ! (0.0..=1.0).contains(&alpha) {
+            return Err(record.error("'alpha' has to be between 0 and 1"));
+        }
+
+        Ok (
+            Layer {
+                image: record.string("image")?,
+                scroll: record.float_or("scroll", 1.0)?,
+                offset: record.float_or("offset", 0.0)?,
+                tiling,
+                alpha,
+            }
+        )
+    }
+}
+
+/// *********************************************************************
+/// Define a struct holding one sprite batch per background layer of the
+/// current arena. They're built when the arena loads and refilled every
+/// frame, so each layer is a single draw. Layers whose image didn't load
+/// have no batch.
+/// *********************************************************************
+
+pub struct LayerBatches {
+    pub batches: Vec<Option<SpriteBatch>>,
+}
+
+impl LayerBatches {
+    pub fn new(layers: &[Layer], assets: &Assets) -> LayerBatches {
+        LayerBatches {
+            batches: layers.iter().map(|l| assets.background(&l.image).map(new_batch)).collect(),
+        }
+    }
+}
+
+/// *********************************************************************
+/// Draw background layers back to front, with the camera's top left
+/// corner at `camera`. Each layer's batch is filled with enough copies of
+/// its image to cover the screen.
+/// *********************************************************************
+
+pub fn draw_layers(ctx: &mut Context, layers: &[Layer], batches: &mut LayerBatches, assets: &Assets, camera: Point2<f32>, stats: &mut DrawStats) -> GameResult {
+    for (layer, batch) in layers.iter().zip(batches.batches.iter_mut()) {
+        let (batch, image) = match (batch, assets.background(&layer.image)) {
+            (Some(batch), Some(image)) => (batch, image),
+            _ => continue,
+        };
+
+        let w = image.width() as f32;
+        let h = image.height() as f32;
+
+        let mut x = -camera.x * layer.scroll;
+        let mut y = layer.offset - camera.y * layer.scroll;
+
+        // Start from the copy just off the left or top of the screen
+        if layer.tiling == Tiling::X || layer.tiling == Tiling::Both {
+            x = x.rem_euclid(w) - w;
+        }
+
+        if layer.tiling == Tiling::Y || layer.tiling == Tiling::Both {
+            y = y.rem_euclid(h) - h;
+        }
+
+        batch.clear();
+        let mut count = 0;
+        let mut row = y;
+
+        loop {
+            let mut col = x;
+
+            loop {
+                let dest = Point2 {x: col.round(), y: row.round()};
+                let drawparams = DrawParam::new().dest(dest).color(Color::new(1.0, 1.0, 1.0, layer.alpha));
+                batch.add(drawparams);
+                count += 1;
+
+                col += w;
+
+                if layer.tiling == Tiling::None || layer.tiling == Tiling::Y || col >= SCREEN_WIDTH {
+                    break;
+                }
+            }
+
+            row += h;
+
+            if layer.tiling == Tiling::None || layer.tiling == Tiling::X || row >= SCREEN_HEIGHT {
+                break;
+            }
+        }
+
+        graphics::draw(ctx, &*batch, DrawParam::new())?;
+        stats.add(count);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Rect};
+use ggez::mint::Point2;
+
+use crate::entity::*;
+
+/// How far the player can move from the middle of the screen before the
+/// camera starts to follow, in pixels.
+const DEAD_ZONE_WIDTH: f32 = 48.0;
+const DEAD_ZONE_HEIGHT: f32 = 32.0;
+
+/// How much of the way to where it wants to be the camera moves each
+/// frame.
+const SMOOTHING: f32 = 0.15;
+
+/// *********************************************************************
+/// Define a struct for the camera, which shows a screen sized part of
+/// the world. Positions are in pixels.
+/// *********************************************************************
+
+pub struct Camera {
+    pub pos: Point2<f32>,
+}
+
+impl Camera {
+    pub fn new() -> Camera {
+        Camera {pos: Point2 {x: 0.0, y: 0.0}}
+    }
+
+    /// Move towards keeping `target` inside the dead zone in the middle of
+    /// the screen, without showing anything outside of `bounds`.
+    pub fn follow(&mut self, target: Point2<f32>, bounds: (f32, f32)) {
+        let wanted = self.wanted(target, bounds);
+
+        self.pos.x += (wanted.x - self.pos.x) * SMOOTHING;
+        self.pos.y += (wanted.y - self.pos.y) * SMOOTHING;
+
+        // Stop drifting once close enough to not be seen moving
+        if (wanted.x - self.pos.x).abs() < 0.1 {
+            self.pos.x = wanted.x;
+        }
+
+        if (wanted.y - self.pos.y).abs() < 0.1 {
+            self.pos.y = wanted.y;
+        }
+    }
+
+    /// Jump straight to `target`, like at the start of a run.
+    pub fn snap(&mut self, target: Point2<f32>, bounds: (f32, f32)) {
+        self.pos = Point2 {x: target.x - SCREEN_WIDTH / 2.0, y: target.y - SCREEN_HEIGHT / 2.0};
+        self.pos = clamp(self.pos, bounds);
+    }
+
+    fn wanted(&self, target: Point2<f32>, bounds: (f32, f32)) -> Point2<f32> {
+        let left = self.pos.x + (SCREEN_WIDTH - DEAD_ZONE_WIDTH) / 2.0;
+        let top = self.pos.y + (SCREEN_HEIGHT - DEAD_ZONE_HEIGHT) / 2.0;
+        let mut wanted = self.pos;
+
+        if target.x < left {
+            wanted.x -= left - target.x;
+        } else if target.x > left + DEAD_ZONE_WIDTH {
+            wanted.x += target.x - left - DEAD_ZONE_WIDTH;
+        }
+
+        if target.y < top {
+            wanted.y -= top - target.y;
+        } else if target.y > top + DEAD_ZONE_HEIGHT {
+            wanted.y += target.y - top - DEAD_ZONE_HEIGHT;
+        }
+
+        clamp(wanted, bounds)
+    }
+
+    /// Draw everything after this in world coordinates, as seen by the
+    /// camera moved by `shake`.
+    pub fn apply(&self, ctx: &mut Context, shake: Point2<f32>) -> GameResult {
+        let x = (self.pos.x + shake.x).round();
+        let y = (self.pos.y + shake.y).round();
+
+        graphics::set_screen_coordinates(ctx, Rect::new(x, y, SCREEN_WIDTH, SCREEN_HEIGHT))
+    }
+
+    /// Go back to drawing in screen coordinates, for the HUD and menus.
+    pub fn reset(&self, ctx: &mut Context) -> GameResult {
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT))
+    }
+}
+
+/// Keep the camera inside a world `bounds` pixels big. Worlds smaller than
+/// the screen stay against the top left.
+fn clamp(pos: Point2<f32>, bounds: (f32, f32)) -> Point2<f32> {
+    Point2 {
+        x: pos.x.min(bounds.0 - SCREEN_WIDTH).max(0.0),
+        y: pos.y.min(bounds.1 - SCREEN_HEIGHT).max(0.0),
+    }
+}
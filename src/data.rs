@@ -0,0 +1,120 @@
+use std::fs;
+use std::path::Path;
+
+use ggez::{Context, GameError, GameResult};
+
+use crate::files::*;
+
+/// *********************************************************************
+/// A record read from one of the game's data files. Data files are
+/// made of records that begin with a '[kind]' header followed by
+/// 'key = value' lines, with '#' starting a comment just like in
+/// settings.cfg.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct Record {
+    pub kind: String,
+    pub file: String,
+    pub line: usize,
+    pub fields: Vec<(String, String)>,
+}
+
+impl Record {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Every value given for a key that can appear more than once.
+    pub fn all(&self, key: &str) -> Vec<&str> {
+        self.fields.iter().filter(|(k, _)| k == key).map(|(_, v)| v.as_str()).collect()
+    }
+
+    pub fn string(&self, key: &str) -> GameResult<String> {
+        match self.get(key) {
+            Some(v) => Ok(v.to_owned()),
+            None => Err(self.error(&format!("missing field '{}'", key))),
+        }
+    }
+
+    pub fn float(&self, key: &str) -> GameResult<f32> {
+        let v = self.string(key)?;
+        v.parse::<f32>().map_err(|_| self.error(&format!("'{}' is not a number", v)))
+    }
+
+    pub fn int(&self, key: &str) -> GameResult<u32> {
+        let v = self.string(key)?;
+        v.parse::<u32>().map_err(|_| self.error(&format!("'{}' is not a whole number", v)))
+    }
+
+    pub fn float_or(&self, key: &str, default: f32) -> GameResult<f32> {
+        match self.get(key) {
+            Some(_) => self.float(key),
+            None => Ok(default),
+        }
+    }
+
+    pub fn int_or(&self, key: &str, default: u32) -> GameResult<u32> {
+        match self.get(key) {
+            Some(_) => self.int(key),
+            None => Ok(default),
+        }
+    }
+
+    pub fn error(&self, msg: &str) -> GameError {
+        GameError::ResourceLoadError(format!("{}: [{}] record on line {}: {}", self.file, self.kind, self.line, msg))
+    }
+}
+
+/// *********************************************************************
+/// Split the text of a data file into records.
+/// *********************************************************************
+
+pub fn parse_records(file: &str, text: &str) -> GameResult<Vec<Record>> {
+    let mut records: Vec<Record> = Vec::new();
+
+    for (i, raw) in text.lines().enumerate() {
+        let line = match raw.find('#') {
+            Some(n) => &raw[..n],
+            None => raw,
+        }.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            records.push(Record {kind: line[1..line.len() - 1].trim().to_owned(), file: file.to_owned(), line: i + 1, fields: Vec::new()});
+        } else if let Some(n) = line.find('=') {
+            let key = line[..n].trim().to_owned();
+            let value = line[n + 1..].trim().to_owned();
+
+            match records.last_mut() {
+                Some(record) => record.fields.push((key, value)),
+                None => return Err(GameError::ResourceLoadError(format!("{}: line {}: '{}' is outside of any record", file, i + 1, key))),
+            }
+        } else {
+            return Err(GameError::ResourceLoadError(format!("{}: line {}: expected '[record]' or 'key = value'", file, i + 1)));
+        }
+    }
+
+    Ok(records)
+}
+
+/// *********************************************************************
+/// Read a data file from the resource directory and parse it.
+/// *********************************************************************
+
+pub fn load_records(ctx: &mut Context, path: &str) -> GameResult<Vec<Record>> {
+    let bytes = read_file(ctx, path)?;
+    parse_records(path, &String::from_utf8_lossy(&bytes))
+}
+
+/// *********************************************************************
+/// Read a data file straight from disk, for when there is no Context.
+/// *********************************************************************
+
+pub fn read_records(path: &Path) -> GameResult<Vec<Record>> {
+    let text = fs::read_to_string(path)?;
+    parse_records(&path.to_string_lossy(), &text)
+}
@@ -1,14 +1,15 @@
 /// *********************************************************************
 /// Use necessary crates.
 /// *********************************************************************
-use ggez::graphics;
-
-use ggez::mint::{Point2, Vector2};
-use ggez::{Context, GameResult};
+use ggez::mint::Point2;
 
 use oorandom::Rand32;
 
+use crate::animation::*;
+use crate::arena::*;
 use crate::assets::*;
+use crate::tilemap::*;
+use crate::wave::*;
 
 const PLAYER_MOVE_RATE: f32 = 2.0;
 const PLAYER_JUMP_VELOCITY: f32 = 23.7;
@@ -17,19 +18,31 @@ const MONSTER_MOVE_RATE: i8 = 2;
 pub const SCREEN_WIDTH: f32 = 320.0;
 pub const SCREEN_HEIGHT: f32 = 240.0;
 pub const PLAYER_JUMP_TIME: f32 = 0.25;
+pub const PLAYER_APEX_TIME: f32 = PLAYER_JUMP_VELOCITY / 9.8;
 pub const GROUND: f32 = SCREEN_HEIGHT - 24.0;
 
 /// *********************************************************************
 /// Create an enumeration of all the different entity types.
 /// *********************************************************************
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum EntityType {
     Player,
     Zombie,
     Skeleton,
 }
 
+impl EntityType {
+    /// Points for stomping this type of entity, before any combo multiplier.
+    pub fn points(&self) -> usize {
+        match self {
+            EntityType::Player => 0,
+            EntityType::Zombie => 1,
+            EntityType::Skeleton => 2,
+        }
+    }
+}
+
 /// *********************************************************************
 /// Create an enumeration of all entity directions.
 /// *********************************************************************
@@ -40,19 +53,6 @@ pub enum Direction {
     Right,
 }
 
-/// *********************************************************************
-/// Create an enumeration of all entity animation frames.
-/// *********************************************************************
-
-#[derive(Clone)]
-pub enum Frame {
-    Stand,
-    Walk1,
-    Walk2,
-    Walk3,
-    Walk4,
-}
-
 /// *********************************************************************
 /// Define a struct containing the properties of an entity.
 /// *********************************************************************
@@ -62,38 +62,23 @@ pub struct Entity {
     pub tag: EntityType,
     pub pos: (i16, i16),
     pub facing: Direction,
-    pub frame: Frame,
+    pub anim: Animator,
     pub falling: bool,
     pub jump: f32,
     pub jump_from: i16,
     pub health: i8,
     pub ticks: i8,
+    pub speed: f32,
 }
 
 /// *********************************************************************
-/// Create a function to draw entities.
+/// Create a function to advance an entity's animation using the timing
+/// from its sprite sheet.
 /// *********************************************************************
 
-pub fn draw_entity(assets: &mut Assets, ctx: &mut Context, entity: &Entity, coords: (i16, i16), scale: f32) -> GameResult {
-    let pos = pos_to_p2(coords);
-
-    let image = assets.image(entity);
-    let drawparams = graphics::DrawParam::new().dest(pos).scale(Vector2{x: scale, y: scale});
-
-    graphics::draw(ctx, image, drawparams)
-}
-
-/// *********************************************************************
-/// Create a function to advance entity animations.
-/// *********************************************************************
-fn advance_animation(entity: &mut Entity) {
-    match entity.frame {
-        Frame::Stand => entity.frame = Frame::Walk1,
-        Frame::Walk1 => entity.frame = Frame::Walk2,
-        Frame::Walk2 => entity.frame = Frame::Walk3,
-        Frame::Walk3 => entity.frame = Frame::Walk4,
-        Frame::Walk4 => entity.frame = Frame::Stand,
-    }
+pub fn animate_entity(entity: &mut Entity, assets: &Assets) {
+    let sheet = assets.sheet(&entity.tag);
+    entity.anim.update(sheet.def.animation(entity.anim.clip.name()));
 }
 
 /// *********************************************************************
@@ -101,69 +86,74 @@ fn advance_animation(entity: &mut Entity) {
 /// properties accordingly.
 /// *********************************************************************
 
-pub fn handle_player_input(entity: &mut Entity, input: &mut InputState, scale: f32) {
-    entity.pos.0 += (PLAYER_MOVE_RATE * input.x * scale) as i16;
-
-    // Make sure the player can't go off the edge of the screen
-    if entity.pos.0 < 0 {
-        entity.pos = (0, entity.pos.1);
-    } else if entity.pos.0 > (SCREEN_WIDTH * scale) as i16 - 16 * scale as i16 {
-        entity.pos = ((SCREEN_WIDTH * scale) as i16 - 16 * scale as i16, entity.pos.1);
-    }
+pub fn handle_player_input(entity: &mut Entity, input: &mut InputState, map: &Tilemap) {
+    let (x, _) = map.move_x(entity.pos, (PLAYER_MOVE_RATE * input.x) as i16);
+    entity.pos.0 = x;
 
-    if input.x != 0.0 {
-        advance_animation(entity);
-    } else if input.x == 0.0 {
-        entity.frame = Frame::Stand;
+    // Walking off the edge of a platform drops the player from there
+    if ! entity.falling && ! map.on_floor(entity.pos) {
+        start_fall(entity);
     }
 
-    if input.jump &&! input.jump_spam {
+    if input.jump && ! input.jump_spam && ! entity.falling {
         entity.falling = true;
-        if entity.jump_from == GROUND as i16 {
-            entity.jump_from = GROUND as i16 - 1;
-        }
-        entity.pos = (entity.pos.0, (((4.9 * entity.jump.powf(2.0)) - (PLAYER_JUMP_VELOCITY * entity.jump) + entity.jump_from as f32 / scale) * scale) as i16);
-
-        if entity.pos.1 >= (GROUND * scale) as i16 {
-
-            entity.pos = (entity.pos.0, (GROUND * scale) as i16);
-            entity.jump = 0.0;
-            input.jump_spam = true;
-
-        }
-
-    } else if ! input.jump {
+        entity.jump = 0.0;
+        entity.jump_from = entity.pos.1;
+    }
 
-        if entity.pos.1 >= (GROUND * scale) as i16 {
+    if entity.falling {
+        let y = ((4.9 * entity.jump.powf(2.0)) - (PLAYER_JUMP_VELOCITY * entity.jump) + entity.jump_from as f32) as i16;
 
+        match map.move_y(entity.pos, y) {
+            (y, Contact::Floor) => {
+                entity.pos.1 = y;
                 entity.falling = false;
                 entity.jump = 0.0;
-                entity.pos = (entity.pos.0, (GROUND * scale) as i16);
-
-        } else if entity.pos.1 < (GROUND * scale) as i16 {
-
-            entity.pos = (entity.pos.0, (((4.9 * entity.jump.powf(2.0)) - (PLAYER_JUMP_VELOCITY * entity.jump) + entity.jump_from as f32 / scale) * scale) as i16);
 
-            if entity.pos.1 >= (GROUND * scale) as i16 {
-
-                entity.falling = false;
-                entity.jump = 0.0;
-                entity.pos = (entity.pos.0, (GROUND * scale) as i16);
+                // Holding jump doesn't jump again until it's let go
+                input.jump_spam = input.jump;
+            }
 
+            (y, Contact::Ceiling) => {
+                entity.pos.1 = y;
+                start_fall(entity);
             }
 
+            (y, Contact::None) => entity.pos.1 = y,
         }
+    }
 
+    if ! input.jump {
         input.jump_spam = false;
     }
 
-    if input.jump_spam {
-        entity.falling = false;
-    }
+    // Pick the animation from what the player is doing
+    let clip = if input.attack {
+        Clip::Attack
+    } else if entity.falling && entity.jump < PLAYER_APEX_TIME {
+        Clip::Jump
+    } else if entity.falling {
+        Clip::Fall
+    } else if input.x.abs() > 1.0 {
+        Clip::Run
+    } else if input.x != 0.0 {
+        Clip::Walk
+    } else {
+        Clip::Idle
+    };
 
-    if entity.falling {
-        entity.frame = Frame::Walk2;
-    }
+    entity.anim.request(clip);
+}
+
+/// *********************************************************************
+/// Put the player at the top of a jump from where they are now, so they
+/// fall from there.
+/// *********************************************************************
+
+fn start_fall(entity: &mut Entity) {
+    entity.falling = true;
+    entity.jump = PLAYER_APEX_TIME;
+    entity.jump_from = entity.pos.1 + (PLAYER_JUMP_VELOCITY * PLAYER_APEX_TIME - 4.9 * PLAYER_APEX_TIME.powf(2.0)) as i16;
 }
 
 /// *********************************************************************
@@ -192,17 +182,18 @@ impl Default for InputState {
 /// Create a function to spawn monsters.
 /// *********************************************************************
 
-pub fn spawn_monsters(rng: &mut Rand32, monster_list: &mut Vec<Entity>, difficulty: u32) {
-    for _ in 0..difficulty {
-        let tag_gen = rng.rand_range(0..2);
-        let tag = if tag_gen == 0 {
-            EntityType::Zombie
+pub fn spawn_monsters(rng: &mut Rand32, monster_list: &mut Vec<Entity>, count: u32, wave: &WaveDef, arena: &Arena) {
+    for _ in 0..count {
+        let tag = wave.pick_monster(rng, &arena.monsters);
+
+        // Arenas without spawn points drop monsters anywhere along the top,
+        // or at the left edge of one too narrow for that
+        let pos = if arena.spawns.is_empty() {
+            (rng.rand_range(16..(arena.map.size().0 - 32.0).max(17.0) as u32) as i16, 8)
         } else {
-            EntityType::Skeleton
+            arena.spawns[rng.rand_range(0..arena.spawns.len() as u32) as usize]
         };
 
-        let pos = (rng.rand_range(16..(SCREEN_WIDTH - 32.0) as u32) as i16, 8);
-
         let facing_gen = rng.rand_range(0..2);
         let facing = if facing_gen == 0 {
             Direction::Left
@@ -214,63 +205,73 @@ pub fn spawn_monsters(rng: &mut Rand32, monster_list: &mut Vec<Entity>, difficul
             tag,
             pos,
             facing,
-            frame: Frame::Walk2,
+            anim: Animator::new(Clip::Fall),
             falling: true,
             jump: 0.0,
             jump_from: pos.1,
             health: 1,
             ticks: 0,
+            speed: wave.speed,
         };
 
         monster_list.push(monster);
     }
 }
 
-/// *********************************************************************
-/// Create a function to draw monsters.
-/// *********************************************************************
-pub fn draw_monsters(monster_list: &mut Vec<Entity>, assets: &mut Assets, ctx: &mut Context, scale: f32) -> GameResult {
-    Ok (
-        for monster in monster_list {
-            draw_entity(assets, ctx, monster, monster.pos, scale)?;
-        }
-    )
-}
-
 /// *********************************************************************
 /// Create a function to update monsters.
 /// *********************************************************************
-pub fn update_monsters(monster_list: &mut Vec<Entity>, scale: f32) {
+pub fn update_monsters(monster_list: &mut Vec<Entity>, map: &Tilemap) {
     for monster in monster_list {
         if ! monster.falling {
             monster.ticks += 1;
         }
-        if monster.ticks == MONSTER_MOVE_RATE && ! monster.falling {
+        // Faster monsters take a step in fewer ticks.
+        let move_rate = ((MONSTER_MOVE_RATE as f32 / monster.speed).round() as i8).max(1);
+
+        if monster.ticks >= move_rate && ! monster.falling {
             let direction = match monster.facing {
-                Direction::Left => -1 * scale as i16,
-                Direction::Right => 1 * scale as i16,
+                Direction::Left => -1,
+                Direction::Right => 1,
             };
 
-            monster.pos = (monster.pos.0 + direction, monster.pos.1);
-            if monster.pos.0 < 0 {
-                monster.facing = Direction::Right;
-                monster.pos = (0, monster.pos.1);
-            } else if monster.pos.0 > ((SCREEN_WIDTH - 16.0) * scale) as i16 {
-                monster.facing = Direction::Left;
-                monster.pos = (((SCREEN_WIDTH - 16.0) * scale) as i16, monster.pos.1);
+            // Turn around at walls and the sides of the map
+            let (x, blocked) = map.move_x(monster.pos, direction);
+            monster.pos = (x, monster.pos.1);
+
+            if blocked {
+                monster.facing = match monster.facing {
+                    Direction::Left => Direction::Right,
+                    Direction::Right => Direction::Left,
+                };
             }
-            advance_animation(monster);
+
             monster.ticks = 0;
+
+            // Walking off the edge of a platform
+            if ! map.on_floor(monster.pos) {
+                monster.falling = true;
+                monster.jump = 0.0;
+                monster.jump_from = monster.pos.1;
+            }
         }
 
         if monster.falling {
-            monster.pos = (monster.pos.0, (((1.09 * monster.jump.powf(2.0)) - 8.0) * scale) as i16);
+            let y = monster.jump_from + (1.09 * monster.jump.powf(2.0)) as i16;
+            let (y, contact) = map.move_y(monster.pos, y);
+
+            monster.pos = (monster.pos.0, y);
             monster.jump += 1.0;
+
+            if contact == Contact::Floor {
+                monster.falling = false;
+            }
         }
 
-        if monster.pos.1 >= (GROUND * scale) as i16 {
-            monster.falling = false;
-            monster.pos = (monster.pos.0, (GROUND * scale) as i16);
+        if monster.falling {
+            monster.anim.request(Clip::Fall);
+        } else {
+            monster.anim.request(Clip::Walk);
         }
     }
 }
@@ -278,8 +279,8 @@ pub fn update_monsters(monster_list: &mut Vec<Entity>, scale: f32) {
 /// *********************************************************************
 /// Detect entity collisions.
 /// *********************************************************************
-pub fn is_touching(entity1: &Entity, entity2: &Entity, scale: f32) -> bool {
-    if (entity1.pos.0 - entity2.pos.0).abs() <= 16 * scale as i16 && (entity1.pos.1 - entity2.pos.1).abs() <= 16 * scale as i16 {
+pub fn is_touching(entity1: &Entity, entity2: &Entity) -> bool {
+    if (entity1.pos.0 - entity2.pos.0).abs() <= 16 && (entity1.pos.1 - entity2.pos.1).abs() <= 16 {
         return true;
     } else {
         return false;
@@ -294,6 +295,6 @@ pub fn is_touching(entity1: &Entity, entity2: &Entity, scale: f32) -> bool {
 /// Convert entity cordinates to a Point2 type.
 /// *********************************************************************
 
-fn pos_to_p2(coords: (i16, i16)) -> Point2<f32> {
+pub fn pos_to_p2(coords: (i16, i16)) -> Point2<f32> {
     Point2 {x: coords.0 as f32, y: coords.1 as f32}
 }
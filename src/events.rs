@@ -0,0 +1,104 @@
+use crate::entity::*;
+
+/// *********************************************************************
+/// Create an enumeration of everything that happens in the simulation
+/// that something outside of it might want to react to. Positions are
+/// the entity's top left corner, in the same units as Entity::pos.
+/// *********************************************************************
+
+#[derive(Clone, PartialEq)]
+pub enum Event {
+    MonsterSpawned {pos: (i16, i16)},
+    MonsterStomped {tag: EntityType, pos: (i16, i16), points: usize},
+    PlayerJumped,
+    PlayerLanded {pos: (i16, i16)},
+    PlayerDamaged {pos: (i16, i16)},
+    PlayerDied {pos: (i16, i16)},
+    WaveStarted {number: u32},
+    ScoreChanged {score: usize},
+}
+
+/// *********************************************************************
+/// Define a struct for the queue the world pushes events into as it
+/// updates. Whoever runs the world drains it once a frame and hands each
+/// event to the audio, particles and anything else that cares, so the
+/// simulation doesn't need to know about any of them.
+/// *********************************************************************
+
+#[derive(Default)]
+pub struct Events {
+    queue: Vec<Event>,
+}
+
+impl Events {
+    pub fn push(&mut self, event: Event) {
+        self.queue.push(event);
+    }
+
+    /// Take every queued event, oldest first, leaving the queue empty.
+    pub fn drain(&mut self) -> std::vec::Drain<'_, Event> {
+        self.queue.drain(..)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::*;
+    use crate::data::*;
+    use crate::wave::*;
+    use crate::world::*;
+
+    const WAVES: &str = "
+[wave]
+spawn = 1
+batch = 1
+interval = 1.0
+intermission = 1.0
+
+[endless]
+spawn = 0
+batch = 0
+interval = 0.0
+intermission = 0.0
+";
+
+    fn world_with_monster() -> World {
+        let waves = Waves::from_records(&parse_records("waves.cfg", WAVES).unwrap()).unwrap();
+        let mut world = World::new(waves, Arena::fallback(), 1);
+
+        // A zombie standing right where the player is
+        let mut monster = world.player.clone();
+        monster.tag = EntityType::Zombie;
+        world.monsters.push(monster);
+
+        world
+    }
+
+    #[test]
+    fn stomp() {
+        let mut world = world_with_monster();
+        world.player.falling = true;
+        world.update(false);
+
+        let events: Vec<Event> = world.events.drain().collect();
+
+        assert!(world.monsters.is_empty());
+        assert!(events.iter().any(|e| matches!(e, Event::MonsterStomped {tag: EntityType::Zombie, ..})));
+        assert!(events.contains(&Event::ScoreChanged {score: world.score}));
+        assert!(! events.iter().any(|e| matches!(e, Event::PlayerDamaged {..})));
+        assert!(world.events.drain().next().is_none());
+    }
+
+    #[test]
+    fn hit() {
+        let mut world = world_with_monster();
+        world.update(false);
+
+        let events: Vec<Event> = world.events.drain().collect();
+
+        assert_eq!(world.monsters.len(), 1);
+        assert!(events.contains(&Event::PlayerDamaged {pos: world.player.pos}));
+        assert!(! events.iter().any(|e| matches!(e, Event::MonsterStomped {..} | Event::ScoreChanged {..})));
+    }
+}
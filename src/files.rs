@@ -0,0 +1,48 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use ggez::{filesystem, Context, GameResult};
+
+// The table of files compiled into the executable by build.rs, if any.
+
+#[cfg(feature = "embedded-assets")]
+include!(concat!(env!("OUT_DIR"), "/embedded.rs"));
+
+#[cfg(not(feature = "embedded-assets"))]
+pub static EMBEDDED: &[(&str, &[u8])] = &[];
+
+/// Find a file compiled into the executable by its resource path.
+pub fn embedded(path: &str) -> Option<&'static [u8]> {
+    EMBEDDED.iter().find(|(p, _)| *p == path).map(|(_, bytes)| *bytes)
+}
+
+/// *********************************************************************
+/// Read a resource file. Resource packs and the resources directory are
+/// checked first, so files on disk always win over embedded ones.
+/// *********************************************************************
+
+pub fn read_file(ctx: &mut Context, path: &str) -> GameResult<Vec<u8>> {
+    let result = filesystem::open(ctx, path).and_then(|mut file| {
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        Ok(bytes)
+    });
+
+    match (result, embedded(path)) {
+        (Err(_), Some(bytes)) => Ok(bytes.to_vec()),
+        (result, _) => result,
+    }
+}
+
+/// *********************************************************************
+/// Read a resource file straight from disk, for when there is no
+/// Context, falling back to the embedded copy of `path`.
+/// *********************************************************************
+
+pub fn read_disk_file(file: &Path, path: &str) -> GameResult<Vec<u8>> {
+    match (fs::read(file), embedded(path)) {
+        (Err(_), Some(bytes)) => Ok(bytes.to_vec()),
+        (result, _) => Ok(result?),
+    }
+}
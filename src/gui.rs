@@ -1,43 +1,820 @@
 use ggez::{GameResult, Context};
-use ggez::graphics::{self, DrawParam};
-use ggez::mint::{Point2, Vector2};
+use ggez::event::{Button, KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Image, Mesh, Rect};
+use ggez::mint::Point2;
+
+use crate::entity::*;
+use crate::lang::*;
+use crate::settings::*;
+use crate::text::*;
+
+/// The size menu text is drawn at.
+const TEXT_SIZE: f32 = 8.0;
+
+/// How far apart the lines of a list are.
+const LINE_HEIGHT: f32 = 12.0;
+
+/// How many steps a slider has from one end to the other.
+const SLIDER_STEPS: usize = 10;
+
+/// How far menus anchored to an edge stay from it.
+const MARGIN: f32 = 8.0;
+
+/// What the selected line of a list is marked with.
+const MARKER: &str = ">";
+
+/// How far the values of a list are from its widest line.
+const COLUMN_GAP: f32 = 8.0;
+
+/// How far a focused image button lifts up.
+const LIFT: f32 = 4.0;
+
+/// How far the focus outline is drawn outside of the focused widget.
+const OUTLINE: f32 = 2.0;
 
 #[derive(PartialEq)]
 pub enum Screen {
     Title,
     Game,
     Death,
+    Packs,
+    Arenas,
+    Sound,
+}
+
+/// *********************************************************************
+/// Create enumerations for how widgets are laid out: which way a stack
+/// goes, and where things sit in the space they're given. An anchor is
+/// where a panel sits on the screen, across and then down.
+/// *********************************************************************
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Axis {
+    Vertical,
+    Horizontal,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+}
+
+pub type Anchor = (Align, Align);
+
+/// *********************************************************************
+/// Create an enumeration of the ways of moving around a menu, so the
+/// keyboard and a gamepad drive menus the same way.
+/// *********************************************************************
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Nav {
+    Up,
+    Down,
+    Left,
+    Right,
+    Next,
+    Prev,
+    Confirm,
+    Back,
+}
+
+impl Nav {
+    /// The arrow keys move, tab and shift+tab go through every widget,
+    /// return and space use the focused one, and escape goes back.
+    pub fn from_key(keycode: KeyCode, keymods: KeyMods) -> Option<Nav> {
+        match keycode {
+            KeyCode::Up => Some(Nav::Up),
+            KeyCode::Down => Some(Nav::Down),
+            KeyCode::Left => Some(Nav::Left),
+            KeyCode::Right => Some(Nav::Right),
+            KeyCode::Tab if keymods.contains(KeyMods::SHIFT) => Some(Nav::Prev),
+            KeyCode::Tab => Some(Nav::Next),
+            KeyCode::Return | KeyCode::NumpadEnter => Some(Nav::Confirm),
+            KeyCode::Space => Some(Nav::Confirm),
+            KeyCode::Escape => Some(Nav::Back),
+            _ => None,
+        }
+    }
+
+    /// The d-pad moves, the shoulder buttons go through every widget, the
+    /// bottom face button or start uses the focused one, and the right
+    /// face button goes back.
+    pub fn from_button(button: Button) -> Option<Nav> {
+        match button {
+            Button::DPadUp => Some(Nav::Up),
+            Button::DPadDown => Some(Nav::Down),
+            Button::DPadLeft => Some(Nav::Left),
+            Button::DPadRight => Some(Nav::Right),
+            Button::RightTrigger => Some(Nav::Next),
+            Button::LeftTrigger => Some(Nav::Prev),
+            Button::South | Button::Start => Some(Nav::Confirm),
+            Button::East => Some(Nav::Back),
+            _ => None,
+        }
+    }
+}
+
+/// *********************************************************************
+/// Create a function for which mouse button uses menus.
+/// *********************************************************************
+
+pub fn menu_button_from_settings() -> MouseButton {
+    match fetch_setting_or("menu_mouse_button", SType::String("left".to_owned())) {
+        SType::String(t) if t == "right" => MouseButton::Right,
+        SType::String(t) if t == "middle" => MouseButton::Middle,
+        _ => MouseButton::Left,
+    }
 }
 
-pub struct Button {
-    pub pos: (i16, i16),
-    pub width: i16,
-    pub scale: f32,
-    pub hover: bool,
+/// *********************************************************************
+/// Create an enumeration of every kind of widget, with the state each
+/// one keeps. Slider values go from 0 to 1. `column` is the least room
+/// a slider's name takes, so sliders stacked together line up, and
+/// `track` is where its steps start and how wide they are, from the last
+/// layout. A list can have a value for each line, shown in a column
+/// beside it.
+/// *********************************************************************
+
+pub enum Kind {
+    Label {text: String, size: f32},
+    ImageButton {image: Image},
+    TextButton {text: String},
+    Toggle {text: String, on: bool},
+    Slider {text: String, value: f32, column: f32, track: (f32, f32)},
+    List {items: Vec<String>, values: Vec<String>, selected: usize},
+    Stack {axis: Axis, align: Align, spacing: f32, children: Vec<Widget>},
 }
 
-impl Button {
-    pub fn draw(ctx: &mut Context, button: &mut Button, image: &mut graphics::Image) -> GameResult {
-        let button_dest = Point2 {x: button.pos.0 as f32 * button.scale, y: button.pos.1 as f32 * button.scale};
-        let drawparams = DrawParam::new().dest(button_dest).scale(Vector2 {x: button.scale, y: button.scale});
-        graphics::draw(ctx, image, drawparams)
+/// *********************************************************************
+/// Define a struct for one widget. Widgets that can be used have an id
+/// that menus report back when they're pressed or changed. Sizes are in
+/// pixels; a width or height of 0 fits the contents. `rect` is where the
+/// widget was last laid out.
+/// *********************************************************************
+
+pub struct Widget {
+    pub id: &'static str,
+    pub kind: Kind,
+    pub width: f32,
+    pub height: f32,
+    pub padding: f32,
+    pub rect: Rect,
+}
+
+impl Widget {
+    fn new(id: &'static str, kind: Kind) -> Widget {
+        Widget {id, kind, width: 0.0, height: 0.0, padding: 0.0, rect: Rect::new(0.0, 0.0, 0.0, 0.0)}
+    }
+
+    pub fn label(text: &str) -> Widget {
+        Widget::new("", Kind::Label {text: text.to_owned(), size: TEXT_SIZE})
+    }
+
+    pub fn title(text: &str) -> Widget {
+        Widget::new("", Kind::Label {text: text.to_owned(), size: TEXT_SIZE * 2.0})
+    }
+
+    pub fn image_button(id: &'static str, image: &Image) -> Widget {
+        Widget::new(id, Kind::ImageButton {image: image.clone()})
+    }
+
+    pub fn text_button(id: &'static str, text: &str) -> Widget {
+        Widget::new(id, Kind::TextButton {text: text.to_owned()})
+    }
+
+    pub fn toggle(id: &'static str, text: &str, on: bool) -> Widget {
+        Widget::new(id, Kind::Toggle {text: text.to_owned(), on})
+    }
+
+    pub fn slider(id: &'static str, text: &str, value: f32) -> Widget {
+        Widget::new(id, Kind::Slider {text: text.to_owned(), value: value.clamp(0.0, 1.0), column: 0.0, track: (0.0, 0.0)})
+    }
+
+    pub fn list(id: &'static str, items: Vec<String>, selected: usize) -> Widget {
+        let selected = selected.min(items.len().saturating_sub(1));
+        Widget::new(id, Kind::List {items, values: Vec::new(), selected})
+    }
+
+    pub fn stack(axis: Axis, align: Align, spacing: f32, children: Vec<Widget>) -> Widget {
+        Widget::new("", Kind::Stack {axis, align, spacing, children})
+    }
+
+    /// Give the widget a fixed size instead of fitting its contents.
+    pub fn size(mut self, width: f32, height: f32) -> Widget {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Leave room around the widget's contents.
+    pub fn padding(mut self, padding: f32) -> Widget {
+        self.padding = padding;
+        self
+    }
+
+    /// Give each line of a list a value to show beside it.
+    pub fn values(mut self, new: Vec<String>) -> Widget {
+        if let Kind::List {values, ..} = &mut self.kind {
+            *values = new;
+        }
+
+        self
+    }
+
+    /// Give every slider in a stack the same column, past the longest of
+    /// their names, so their steps line up.
+    fn align_sliders(&mut self, ctx: &Context, font: graphics::Font) {
+        let style = TextStyle::new(font, TEXT_SIZE, Color::BLACK);
+
+        if let Kind::Stack {children, ..} = &mut self.kind {
+            let names = children.iter().filter_map(|c| match &c.kind {
+                Kind::Slider {text, ..} => Some(measure(ctx, &format!("{} ", text), &style).0),
+                _ => None,
+            });
+            let widest = names.fold(0.0, f32::max);
+
+            for child in children.iter_mut() {
+                if let Kind::Slider {column, ..} = &mut child.kind {
+                    *column = widest;
+                }
+
+                child.align_sliders(ctx, font);
+            }
+        }
+    }
+
+    /// Whether the widget can be focused and used.
+    fn interactive(&self) -> bool {
+        ! matches!(self.kind, Kind::Label {..} | Kind::Stack {..})
+    }
+
+    /// How much room the widget needs, padding included.
+    fn measure(&self, ctx: &Context, font: graphics::Font) -> (f32, f32) {
+        let style = TextStyle::new(font, TEXT_SIZE, Color::BLACK);
+
+        let (w, h) = match &self.kind {
+            Kind::Label {text, size} => measure(ctx, text, &TextStyle::new(font, *size, Color::BLACK)),
+            Kind::ImageButton {image} => (image.width() as f32, image.height() as f32 + LIFT),
+            Kind::TextButton {text} => measure(ctx, text, &style),
+            Kind::Toggle {text, ..} => {
+                let (w, h) = measure(ctx, text, &style);
+                (toggle_offset(ctx, &style) + w, h)
+            }
+            Kind::Slider {text, column, ..} => {
+                let (w, h) = measure(ctx, &slider_bar(0.0), &style);
+                (slider_offset(ctx, text, *column, &style) + w, h)
+            }
+            Kind::List {items, values, ..} if values.is_empty() => (widest(ctx, items, &style), items.len() as f32 * LINE_HEIGHT),
+            Kind::List {items, values, ..} => {
+                let column = value_column(ctx, items, &style);
+                (column + widest_value(ctx, values, &style), items.len() as f32 * LINE_HEIGHT)
+            }
+            Kind::Stack {axis, spacing, children, ..} => {
+                let sizes: Vec<(f32, f32)> = children.iter().map(|c| c.measure(ctx, font)).collect();
+                let gaps = spacing * children.len().saturating_sub(1) as f32;
+
+                match axis {
+                    Axis::Vertical => (sizes.iter().map(|s| s.0).fold(0.0, f32::max), sizes.iter().map(|s| s.1).sum::<f32>() + gaps),
+                    Axis::Horizontal => (sizes.iter().map(|s| s.0).sum::<f32>() + gaps, sizes.iter().map(|s| s.1).fold(0.0, f32::max)),
+                }
+            }
+        };
+
+        (w.max(self.width) + self.padding * 2.0, h.max(self.height) + self.padding * 2.0)
     }
 
-    pub fn hover(button: &mut Button, mx: f32, my: f32, scale: f32) {
-        if mx >= button.pos.0 as f32 * scale && mx <= (button.pos.0 + button.width) as f32 * scale && my >= button.pos.1 as f32 * scale && my <= (button.pos.1 + button.width) as f32 * scale {
+    /// Place the widget and its children with its top left corner at
+    /// `x`, `y`.
+    fn layout(&mut self, ctx: &Context, font: graphics::Font, x: f32, y: f32) {
+        let (w, h) = self.measure(ctx, font);
+        self.rect = Rect::new(x, y, w, h);
+
+        let padding = self.padding;
+
+        if let Kind::Slider {text, column, track, ..} = &mut self.kind {
+            let style = TextStyle::new(font, TEXT_SIZE, Color::BLACK);
+            let start = slider_offset(ctx, text, *column, &style) + measure(ctx, "[", &style).0;
+            let steps = measure(ctx, &"#".repeat(SLIDER_STEPS), &style).0;
+            *track = (x + padding + start, steps);
+        }
+
+        if let Kind::Stack {axis, align, spacing, children} = &mut self.kind {
+            let inner_w = w - padding * 2.0;
+            let inner_h = h - padding * 2.0;
+            let mut along = 0.0;
 
-            if ! button.hover {
-                button.pos = (button.pos.0, button.pos.1 - (4.0 * scale) as i16);
-                button.hover = true;
+            for child in children.iter_mut() {
+                let (cw, ch) = child.measure(ctx, font);
+
+                let (free, size) = match axis {
+                    Axis::Vertical => (inner_w - cw, ch),
+                    Axis::Horizontal => (inner_h - ch, cw),
+                };
+
+                let across = match align {
+                    Align::Start => 0.0,
+                    Align::Center => (free / 2.0).floor(),
+                    Align::End => free,
+                };
+
+                match axis {
+                    Axis::Vertical => child.layout(ctx, font, x + padding + across, y + padding + along),
+                    Axis::Horizontal => child.layout(ctx, font, x + padding + along, y + padding + across),
+                }
+
+                along += size + *spacing;
             }
+        }
+    }
+
+    fn find(&self, id: &str) -> Option<&Widget> {
+        if self.id == id {
+            return Some(self);
+        }
 
-        } else if button.hover {
-            button.pos = (button.pos.0, button.pos.1 + (4.0 * scale) as i16);
-            button.hover = false;
+        match &self.kind {
+            Kind::Stack {children, ..} => children.iter().find_map(|c| c.find(id)),
+            _ => None,
+        }
+    }
+
+    fn find_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        if self.id == id {
+            return Some(self);
+        }
+
+        match &mut self.kind {
+            Kind::Stack {children, ..} => children.iter_mut().find_map(|c| c.find_mut(id)),
+            _ => None,
+        }
+    }
+
+    /// The ids of every widget that can be focused, in order.
+    fn focusable(&self, ids: &mut Vec<&'static str>) {
+        if self.interactive() {
+            ids.push(self.id);
+        }
+
+        if let Kind::Stack {children, ..} = &self.kind {
+            for child in children {
+                child.focusable(ids);
+            }
+        }
+    }
+
+    /// The id of the widget that can be used under a point.
+    fn at(&self, x: f32, y: f32) -> Option<&'static str> {
+        if let Kind::Stack {children, ..} = &self.kind {
+            return children.iter().find_map(|c| c.at(x, y));
+        }
+
+        if self.interactive() && self.rect.contains(Point2 {x, y}) {
+            Some(self.id)
+        } else {
+            None
         }
     }
 }
 
+/// *********************************************************************
+/// Define a struct for a menu: trees of widgets attached to parts of the
+/// screen, the widget that has the focus, and the widget the mouse button
+/// went down on. Mouse positions are in pixels on the screen.
+/// Everything that uses a widget returns its id if that pressed or
+/// changed it.
+/// *********************************************************************
+
+pub struct Menu {
+    pub panels: Vec<(Widget, Anchor)>,
+    pub focus: Option<&'static str>,
+    pub pressed: Option<&'static str>,
+}
+
+impl Menu {
+    pub fn new(root: Widget, anchor: Anchor) -> Menu {
+        Menu {panels: Vec::new(), focus: None, pressed: None}.panel(root, anchor)
+    }
+
+    /// Attach another tree of widgets to the screen.
+    pub fn panel(mut self, root: Widget, anchor: Anchor) -> Menu {
+        self.panels.push((root, anchor));
+        self
+    }
+
+    /// Start with a widget focused.
+    pub fn with_focus(mut self, id: &'static str) -> Menu {
+        self.focus = Some(id);
+        self
+    }
+
+    /// Place every panel against its anchor, sized to its text in `font`.
+    pub fn layout(&mut self, ctx: &Context, font: graphics::Font) {
+        for (root, anchor) in self.panels.iter_mut() {
+            root.align_sliders(ctx, font);
+            let (w, h) = root.measure(ctx, font);
+            let x = place(anchor.0, w, SCREEN_WIDTH);
+            let y = place(anchor.1, h, SCREEN_HEIGHT);
+
+            root.layout(ctx, font, x, y);
+        }
+    }
+
+    pub fn widget(&self, id: &str) -> Option<&Widget> {
+        self.panels.iter().find_map(|(root, _)| root.find(id))
+    }
+
+    fn widget_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        self.panels.iter_mut().find_map(|(root, _)| root.find_mut(id))
+    }
+
+    /// The id of the widget that can be used under a point.
+    fn at(&self, x: f32, y: f32) -> Option<&'static str> {
+        self.panels.iter().find_map(|(root, _)| root.at(x, y))
+    }
+
+    /// Move the focus to the next widget, wrapping around at the end.
+    pub fn focus_next(&mut self) {
+        self.step_focus(1);
+    }
+
+    /// Move the focus to the previous widget, wrapping around at the start.
+    pub fn focus_prev(&mut self) {
+        self.step_focus(-1);
+    }
+
+    fn step_focus(&mut self, delta: i32) {
+        let mut ids = Vec::new();
+        for (root, _) in &self.panels {
+            root.focusable(&mut ids);
+        }
+
+        if ids.is_empty() {
+            return;
+        }
+
+        let next = match self.focus.and_then(|f| ids.iter().position(|i| *i == f)) {
+            Some(i) => (i as i32 + delta).rem_euclid(ids.len() as i32) as usize,
+            None if delta < 0 => ids.len() - 1,
+            None => 0,
+        };
+
+        self.focus = Some(ids[next]);
+    }
+
+    /// Move around the menu or use the focused widget. Up and down move
+    /// through a focused list before leaving it, and left and right change
+    /// a focused slider or toggle. With nothing focused, the first move
+    /// just focuses something. Going back is up to whoever owns the menu.
+    pub fn navigate(&mut self, nav: Nav) -> Option<&'static str> {
+        let kind = self.focus.and_then(|f| self.widget(f)).map(|w| &w.kind);
+
+        if kind.is_none() {
+            match nav {
+                Nav::Back => (),
+                Nav::Up | Nav::Left | Nav::Prev => self.focus_prev(),
+                _ => self.focus_next(),
+            }
+
+            return None;
+        }
+
+        let list = matches!(kind, Some(Kind::List {..}));
+        let adjustable = matches!(kind, Some(Kind::Slider {..}) | Some(Kind::Toggle {..}));
+
+        match nav {
+            Nav::Up | Nav::Down => {
+                let delta = if nav == Nav::Up { -1 } else { 1 };
+                let moved = if list { self.adjust(delta) } else { None };
+
+                if moved.is_none() {
+                    self.step_focus(delta);
+                }
+
+                moved
+            }
+            Nav::Left if adjustable => self.adjust(-1),
+            Nav::Right if adjustable => self.adjust(1),
+            Nav::Left | Nav::Prev => {
+                self.focus_prev();
+                None
+            }
+            Nav::Right | Nav::Next => {
+                self.focus_next();
+                None
+            }
+            Nav::Confirm => self.activate(),
+            Nav::Back => None,
+        }
+    }
+
+    /// Focus whatever is under the mouse. Returns true if the focus moved
+    /// onto a different widget.
+    pub fn hover(&mut self, x: f32, y: f32) -> bool {
+        match self.at(x, y) {
+            Some(id) if self.focus != Some(id) => {
+                self.focus = Some(id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Note the widget the mouse button went down on. Nothing is used
+    /// until the button comes back up.
+    pub fn press(&mut self, x: f32, y: f32) {
+        self.pressed = self.at(x, y);
+
+        if self.pressed.is_some() {
+            self.focus = self.pressed;
+        }
+    }
+
+    /// Use the widget the mouse button went down on if it's still over it,
+    /// so dragging away before letting go cancels.
+    pub fn release(&mut self, x: f32, y: f32) -> Option<&'static str> {
+        let pressed = self.pressed.take()?;
+
+        if self.at(x, y) != Some(pressed) {
+            return None;
+        }
+
+        self.click(pressed, x, y)
+    }
+
+    /// Use a widget clicked at a point: press buttons, flip toggles, set
+    /// sliders to where they were clicked and pick list items.
+    fn click(&mut self, id: &'static str, x: f32, y: f32) -> Option<&'static str> {
+        self.focus = Some(id);
+
+        let widget = self.widget_mut(id)?;
+        let rect = widget.rect;
+        let padding = widget.padding;
+
+        match &mut widget.kind {
+            Kind::ImageButton {..} | Kind::TextButton {..} => Some(id),
+            Kind::Toggle {on, ..} => {
+                *on = ! *on;
+                Some(id)
+            }
+            Kind::Slider {value, track, ..} => {
+                let along = (x - track.0) / track.1;
+                *value = (along * SLIDER_STEPS as f32).ceil().clamp(0.0, SLIDER_STEPS as f32) / SLIDER_STEPS as f32;
+                Some(id)
+            }
+            Kind::List {items, selected, ..} => {
+                let line = ((y - rect.y - padding) / LINE_HEIGHT).floor().max(0.0) as usize;
+                *selected = line.min(items.len().saturating_sub(1));
+                Some(id)
+            }
+            Kind::Label {..} | Kind::Stack {..} => None,
+        }
+    }
+
+    /// Use the focused widget, like pressing return. Buttons and lists
+    /// report back, toggles flip.
+    pub fn activate(&mut self) -> Option<&'static str> {
+        let id = self.focus?;
+
+        match &mut self.widget_mut(id)?.kind {
+            Kind::ImageButton {..} | Kind::TextButton {..} | Kind::List {..} => Some(id),
+            Kind::Toggle {on, ..} => {
+                *on = ! *on;
+                Some(id)
+            }
+            _ => None,
+        }
+    }
+
+    /// Move the focused slider or list by `delta` steps, or flip the
+    /// focused toggle.
+    pub fn adjust(&mut self, delta: i32) -> Option<&'static str> {
+        let id = self.focus?;
+
+        match &mut self.widget_mut(id)?.kind {
+            Kind::Slider {value, ..} => {
+                let step = 1.0 / SLIDER_STEPS as f32;
+                let next = (*value + step * delta as f32).clamp(0.0, 1.0);
+
+                if (next - *value).abs() < f32::EPSILON {
+                    return None;
+                }
+
+                *value = (next * SLIDER_STEPS as f32).round() / SLIDER_STEPS as f32;
+                Some(id)
+            }
+            Kind::List {items, selected, ..} => {
+                let next = (*selected as i32 + delta).clamp(0, items.len() as i32 - 1).max(0) as usize;
+
+                if next == *selected {
+                    return None;
+                }
+
+                *selected = next;
+                Some(id)
+            }
+            Kind::Toggle {on, ..} => {
+                *on = ! *on;
+                Some(id)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_on(&self, id: &str) -> bool {
+        matches!(self.widget(id).map(|w| &w.kind), Some(Kind::Toggle {on: true, ..}))
+    }
+
+    pub fn value(&self, id: &str) -> f32 {
+        match self.widget(id).map(|w| &w.kind) {
+            Some(Kind::Slider {value, ..}) => *value,
+            _ => 0.0,
+        }
+    }
+
+    pub fn selected(&self, id: &str) -> usize {
+        match self.widget(id).map(|w| &w.kind) {
+            Some(Kind::List {selected, ..}) => *selected,
+            _ => 0,
+        }
+    }
+}
+
+/// *********************************************************************
+/// Define a struct holding the menu for each screen that has one.
+/// *********************************************************************
+
 pub struct Gui {
-    pub play: Button,
-}
\ No newline at end of file
+    pub title: Menu,
+    pub arenas: Menu,
+    pub packs: Menu,
+    pub sound: Menu,
+}
+
+impl Gui {
+    /// The menu shown on a screen, if it has one.
+    pub fn menu_mut(&mut self, screen: &Screen) -> Option<&mut Menu> {
+        match screen {
+            Screen::Title | Screen::Death => Some(&mut self.title),
+            Screen::Arenas => Some(&mut self.arenas),
+            Screen::Packs => Some(&mut self.packs),
+            Screen::Sound => Some(&mut self.sound),
+            _ => None,
+        }
+    }
+}
+
+/// *********************************************************************
+/// Create a function to build the title menu.
+/// *********************************************************************
+
+pub fn title_menu(play: &Image, lang: &Lang) -> Menu {
+    let root = Widget::stack(Axis::Vertical, Align::Center, 8.0, vec![
+        Widget::image_button("play", play),
+        Widget::text_button("packs", lang.tr("resource_packs")).size(64.0, 0.0),
+        Widget::text_button("sound", lang.tr("sound")).size(64.0, 0.0),
+    ]);
+
+    Menu::new(root, (Align::Center, Align::Center)).with_focus("play")
+}
+
+/// *********************************************************************
+/// Create a function to draw a menu. The menu is laid out again first,
+/// so widgets fit their text as it changes. The focused widget is drawn
+/// darker, and a focused image button lifts up.
+/// *********************************************************************
+
+pub fn draw_menu(ctx: &mut Context, menu: &mut Menu, font: graphics::Font) -> GameResult {
+    menu.layout(ctx, font);
+
+    for (root, _) in &menu.panels {
+        draw_widget(ctx, root, menu.focus, font)?;
+    }
+
+    Ok(())
+}
+
+fn draw_widget(ctx: &mut Context, widget: &Widget, focus: Option<&'static str>, font: graphics::Font) -> GameResult {
+    let dark = Color::from_rgb(90, 117, 35);
+    let light = Color::from_rgb(120, 147, 65);
+
+    let focused = widget.interactive() && focus == Some(widget.id);
+    let color = if focused { dark } else { light };
+    let x = widget.rect.x + widget.padding;
+    let y = widget.rect.y + widget.padding;
+
+    // Outline the focused widget, so it's clear what return will use
+    if focused {
+        let r = widget.rect;
+        let outline = Rect::new(r.x - OUTLINE, r.y - OUTLINE, r.w + OUTLINE * 2.0, r.h + OUTLINE * 2.0);
+        let mesh = Mesh::new_rectangle(ctx, DrawMode::stroke(1.0), outline, dark)?;
+        graphics::draw(ctx, &mesh, DrawParam::new())?;
+    }
+
+    let style = TextStyle::new(font, TEXT_SIZE, color);
+
+    match &widget.kind {
+        Kind::Label {text, size} => {
+            let color = if *size > TEXT_SIZE { dark } else { light };
+            draw_text(ctx, text, Point2 {x, y}, &TextStyle::new(font, *size, color))
+        }
+        Kind::ImageButton {image} => {
+            let lift = if focused { 0.0 } else { LIFT };
+            let drawparams = DrawParam::new().dest(Point2 {x, y: y + lift});
+            graphics::draw(ctx, image, drawparams)
+        }
+        Kind::Stack {children, ..} => {
+            for child in children {
+                draw_widget(ctx, child, focus, font)?;
+            }
+
+            Ok(())
+        }
+        Kind::List {items, values, selected} => {
+            let column = x + value_column(ctx, items, &style);
+
+            for (i, item) in items.iter().enumerate() {
+                let color = if i == *selected { dark } else { light };
+                let line_y = y + i as f32 * LINE_HEIGHT;
+                if i == *selected {
+                    draw_text(ctx, MARKER, Point2 {x, y: line_y}, &style.color(color))?;
+                }
+
+                draw_text(ctx, item, Point2 {x: x + marker_offset(ctx, &style), y: line_y}, &style.color(color))?;
+
+                if let Some(value) = values.get(i) {
+                    draw_text(ctx, value, Point2 {x: column, y: line_y}, &style.color(color))?;
+                }
+            }
+
+            Ok(())
+        }
+        Kind::TextButton {text} => {
+            // Buttons wider than their text keep it in the middle
+            let middle = widget.rect.x + widget.rect.w / 2.0;
+            draw_text(ctx, text, Point2 {x: middle, y}, &style.align(graphics::Align::Center))
+        }
+        Kind::Toggle {text, on} => {
+            draw_text(ctx, toggle_box(*on), Point2 {x, y}, &style)?;
+            draw_text(ctx, text, Point2 {x: x + toggle_offset(ctx, &style), y}, &style)
+        }
+        Kind::Slider {text, value, column, ..} => {
+            draw_text(ctx, text, Point2 {x, y}, &style)?;
+            draw_text(ctx, &slider_bar(*value), Point2 {x: x + slider_offset(ctx, text, *column, &style), y}, &style)
+        }
+    }
+}
+
+/// *********************************************************************
+/// Helper functions.
+/// *********************************************************************
+
+/// Where something `size` long starts when placed in `space` with a
+/// margin at either end.
+fn place(align: Align, size: f32, space: f32) -> f32 {
+    match align {
+        Align::Start => MARGIN,
+        Align::Center => ((space - size) / 2.0).floor(),
+        Align::End => space - size - MARGIN,
+    }
+}
+
+fn toggle_box(on: bool) -> &'static str {
+    if on { "[x]" } else { "[ ]" }
+}
+
+/// Where a toggle's text starts, past whichever box is wider.
+fn toggle_offset(ctx: &Context, style: &TextStyle) -> f32 {
+    measure(ctx, &format!("{} ", toggle_box(true)), style).0.max(measure(ctx, &format!("{} ", toggle_box(false)), style).0)
+}
+
+fn slider_bar(value: f32) -> String {
+    let filled = (value * SLIDER_STEPS as f32).round() as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(SLIDER_STEPS - filled))
+}
+
+/// Where a slider's steps start, past its name or its column if that's
+/// further.
+fn slider_offset(ctx: &Context, text: &str, column: f32, style: &TextStyle) -> f32 {
+    measure(ctx, &format!("{} ", text), style).0.max(column)
+}
+
+/// Where the lines of a list start, past the marker on the selected one.
+fn marker_offset(ctx: &Context, style: &TextStyle) -> f32 {
+    measure(ctx, &format!("{} ", MARKER), style).0
+}
+
+/// How wide the widest line of a list is.
+fn widest(ctx: &Context, items: &[String], style: &TextStyle) -> f32 {
+    marker_offset(ctx, style) + items.iter().map(|t| measure(ctx, t, style).0).fold(0.0, f32::max)
+}
+
+/// Where the values of a list start, past its widest line.
+fn value_column(ctx: &Context, items: &[String], style: &TextStyle) -> f32 {
+    widest(ctx, items, style) + COLUMN_GAP
+}
+
+fn widest_value(ctx: &Context, values: &[String], style: &TextStyle) -> f32 {
+    values.iter().map(|t| measure(ctx, t, style).0).fold(0.0, f32::max)
+}
@@ -0,0 +1,130 @@
+use ggez::mint::Point2;
+use oorandom::Rand32;
+
+use crate::events::*;
+use crate::settings::*;
+
+/// How long the screen shakes for after the player is hurt, in frames.
+const SHAKE_TICKS: u32 = 12;
+
+/// How far the screen moves at the start of a full strength shake, in
+/// pixels.
+const SHAKE_PIXELS: f32 = 3.0;
+
+/// How long a hurt sprite flashes white for, in frames.
+const FLASH_TICKS: u32 = 8;
+
+/// *********************************************************************
+/// Define a struct for how strong each effect is, read from
+/// settings.cfg. Reduce motion turns off everything that moves or stops
+/// the screen, whatever the other settings say.
+/// *********************************************************************
+
+pub struct JuiceSettings {
+    pub shake: f32,
+    pub hitstop: u32,
+    pub flash: f32,
+}
+
+impl JuiceSettings {
+    pub fn from_settings() -> JuiceSettings {
+        let reduce_motion = match fetch_setting_or("reduce_motion", SType::Int(0)) {
+            SType::Int(t) => t != 0,
+            _ => false,
+        };
+
+        let shake = match fetch_setting_or("shake_intensity", SType::Float(1.0)) {
+            SType::Float(t) => t.clamp(0.0, 1.0),
+            _ => 1.0,
+        };
+
+        let hitstop = match fetch_setting_or("hitstop_frames", SType::Int(4)) {
+            SType::Int(t) => t.max(0) as u32,
+            _ => 4,
+        };
+
+        let flash = match fetch_setting_or("flash_intensity", SType::Float(1.0)) {
+            SType::Float(t) => t.clamp(0.0, 1.0),
+            _ => 1.0,
+        };
+
+        if reduce_motion {
+            JuiceSettings {shake: 0.0, hitstop: 0, flash}
+        } else {
+            JuiceSettings {shake, hitstop, flash}
+        }
+    }
+}
+
+/// *********************************************************************
+/// Define a struct for the feedback effects: the screen shaking when the
+/// player is hurt, the game holding still for a moment on a stomp, and
+/// the player flashing white when hurt. None of them touch the world, so
+/// they only change what's drawn and when the world is updated.
+/// *********************************************************************
+
+pub struct Juice {
+    pub settings: JuiceSettings,
+    shake: u32,
+    hitstop: u32,
+    flash: u32,
+    rng: Rand32,
+    pub offset: Point2<f32>,
+}
+
+impl Juice {
+    pub fn new(settings: JuiceSettings, seed: u64) -> Juice {
+        Juice {settings, shake: 0, hitstop: 0, flash: 0, rng: Rand32::new(seed), offset: Point2 {x: 0.0, y: 0.0}}
+    }
+
+    /// Start the effects for something that happened in the world.
+    pub fn handle(&mut self, event: &Event) {
+        match event {
+            Event::MonsterStomped {..} => self.hitstop = self.settings.hitstop,
+            Event::PlayerDamaged {..} | Event::PlayerDied {..} => {
+                self.shake = SHAKE_TICKS;
+                self.flash = FLASH_TICKS;
+            }
+            _ => (),
+        }
+    }
+
+    /// Stop every effect, like when a new run starts.
+    pub fn clear(&mut self) {
+        self.shake = 0;
+        self.hitstop = 0;
+        self.flash = 0;
+        self.offset = Point2 {x: 0.0, y: 0.0};
+    }
+
+    /// Count the effects down by a frame. Returns true while the world
+    /// should be held still.
+    pub fn update(&mut self) -> bool {
+        self.offset = Point2 {x: 0.0, y: 0.0};
+
+        if self.shake > 0 && self.settings.shake > 0.0 {
+            // Shake less and less as it wears off
+            let strength = SHAKE_PIXELS * self.settings.shake * self.shake as f32 / SHAKE_TICKS as f32;
+
+            self.offset = Point2 {
+                x: (self.rng.rand_float() * 2.0 - 1.0) * strength,
+                y: (self.rng.rand_float() * 2.0 - 1.0) * strength,
+            };
+        }
+
+        self.shake = self.shake.saturating_sub(1);
+        self.flash = self.flash.saturating_sub(1);
+
+        if self.hitstop > 0 {
+            self.hitstop -= 1;
+            return true;
+        }
+
+        false
+    }
+
+    /// How opaque the white flash over the player is now.
+    pub fn flash_alpha(&self) -> f32 {
+        self.settings.flash * self.flash as f32 / FLASH_TICKS as f32
+    }
+}
@@ -0,0 +1,228 @@
+use std::fmt::Display;
+
+use ggez::{Context, GameResult};
+
+use crate::assets::*;
+use crate::data::*;
+use crate::settings::*;
+
+/// The language every other language falls back to for strings it
+/// doesn't have.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// The forms a plural string can have, picked by how many there are.
+pub const PLURAL_FORMS: [&str; 3] = ["zero", "one", "other"];
+
+// USED_KEYS, every string the code looks up, found by build.rs
+include!(concat!(env!("OUT_DIR"), "/lang_keys.rs"));
+
+/// The file a language's strings are kept in.
+pub fn lang_file(code: &str) -> String {
+    format!("/lang/{}.cfg", code)
+}
+
+/// *********************************************************************
+/// Define a struct holding the strings shown to the player, in the
+/// language picked in settings.cfg. Strings that language doesn't have
+/// come from the default language, and strings neither has show their
+/// key, so a missing string is obvious without stopping the game.
+/// *********************************************************************
+
+pub struct Lang {
+    pub code: String,
+    strings: Vec<(String, String)>,
+    fallback: Vec<(String, String)>,
+}
+
+impl Lang {
+    pub fn new(ctx: &mut Context, report: &mut LoadReport) -> Lang {
+        let code = match fetch_setting_or("language", SType::String(DEFAULT_LANGUAGE.to_owned())) {
+            SType::String(t) => t,
+            _ => DEFAULT_LANGUAGE.to_owned(),
+        };
+
+        let mut lang = Lang {code, strings: Vec::new(), fallback: Vec::new()};
+        lang.reload(ctx, report);
+        lang
+    }
+
+    /// Load the strings again, like after the files change on disk. A file
+    /// that can't be loaded goes in the report and has no strings.
+    pub fn reload(&mut self, ctx: &mut Context, report: &mut LoadReport) {
+        self.fallback = load_strings(ctx, DEFAULT_LANGUAGE, report);
+
+        self.strings = if self.code == DEFAULT_LANGUAGE {
+            Vec::new()
+        } else {
+            load_strings(ctx, &self.code, report)
+        };
+    }
+
+    /// Read the strings from a language file's records.
+    pub fn from_records(records: &[Record]) -> GameResult<Vec<(String, String)>> {
+        let mut strings: Vec<(String, String)> = Vec::new();
+
+        for record in records {
+            if record.kind != "strings" {
+                return Err(record.error("expected a [strings] record"));
+            }
+
+            for (key, value) in &record.fields {
+                if strings.iter().any(|(k, _)| k == key) {
+                    return Err(record.error(&format!("'{}' is given twice", key)));
+                }
+
+                strings.push((key.clone(), value.clone()));
+            }
+        }
+
+        Ok(strings)
+    }
+
+    fn find(&self, key: &str) -> Option<&str> {
+        self.strings.iter().chain(self.fallback.iter()).find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// The string for `key`.
+    pub fn tr<'a>(&'a self, key: &'a str) -> &'a str {
+        self.find(key).unwrap_or(key)
+    }
+
+    /// The string for `key` with every `{name}` in it filled in from
+    /// `args`.
+    pub fn tr_args(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        fill(self.tr(key), args)
+    }
+
+    /// The form of the string for `key` to use for `n` things, with `{n}`
+    /// filled in. Forms are given as `key.zero`, `key.one` and
+    /// `key.other`, and a form a language doesn't have uses the other one.
+    /// The default language is only used when the picked language has no
+    /// form of the string at all.
+    pub fn tr_count(&self, key: &str, n: usize) -> String {
+        let text = plural(&self.strings, key, n)
+            .or_else(|| plural(&self.fallback, key, n))
+            .unwrap_or(key);
+        fill(text, &[("n", &n)])
+    }
+}
+
+/// The form of the string for `key` for `n` things from one language's
+/// strings, if it has any form of it.
+fn plural<'a>(strings: &'a [(String, String)], key: &str, n: usize) -> Option<&'a str> {
+    let find = |form: &str| {
+        let name = if form.is_empty() { key.to_owned() } else { format!("{}.{}", key, form) };
+        strings.iter().find(|(k, _)| *k == name).map(|(_, v)| v.as_str())
+    };
+
+    let first = match n {
+        0 => "zero",
+        1 => "one",
+        _ => "other",
+    };
+
+    // Zero uses the other form unless it has its own, and one uses the
+    // other form if it's missing
+    find(first).or_else(|| find("other")).or_else(|| find("one")).or_else(|| find(""))
+}
+
+fn load_strings(ctx: &mut Context, code: &str, report: &mut LoadReport) -> Vec<(String, String)> {
+    let path = lang_file(code);
+
+    match load_records(ctx, &path).and_then(|r| Lang::from_records(&r)) {
+        Ok(t) => t,
+        Err(e) => {
+            report.add(&path, &e);
+            Vec::new()
+        }
+    }
+}
+
+/// Put the value of each argument in place of its `{name}`.
+fn fill(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = text.to_owned();
+
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EN: &str = "
+[strings]
+play = play
+back = back
+arena_best = best {score}
+batches.one = {n} batch
+batches.other = {n} batches
+sprites.zero = no sprites
+sprites.one = {n} sprite
+sprites.other = {n} sprites
+";
+
+    const FR: &str = "
+[strings]
+play = jouer
+arena_best = record {score}
+batches.other = {n} lots
+sprites.zero = aucun sprite
+sprites.other = {n} sprites
+";
+
+    // Kept out of the calls so build.rs doesn't count it as a used key
+    const MISSING: &str = "not_a_string";
+
+    fn lang(code: &str) -> Lang {
+        let strings = |file: &str, text: &str| Lang::from_records(&parse_records(file, text).unwrap()).unwrap();
+
+        Lang {
+            code: code.to_owned(),
+            strings: if code == DEFAULT_LANGUAGE { Vec::new() } else { strings("fr.cfg", FR) },
+            fallback: strings("en.cfg", EN),
+        }
+    }
+
+    #[test]
+    fn missing_strings_fall_back_to_english() {
+        let fr = lang("fr");
+
+        assert_eq!(fr.tr("play"), "jouer");
+        assert_eq!(fr.tr("back"), "back");
+        assert_eq!(fr.tr_args("arena_best", &[("score", &12)]), "record 12");
+    }
+
+    #[test]
+    fn unknown_strings_show_their_key() {
+        let fr = lang("fr");
+
+        assert_eq!(fr.tr(MISSING), MISSING);
+        assert_eq!(fr.tr_count(MISSING, 2), MISSING);
+    }
+
+    #[test]
+    fn plural_forms_are_picked_by_count() {
+        let en = lang("en");
+
+        assert_eq!(en.tr_count("batches", 1), "1 batch");
+        assert_eq!(en.tr_count("batches", 3), "3 batches");
+        assert_eq!(en.tr_count("batches", 0), "0 batches");
+        assert_eq!(en.tr_count("sprites", 0), "no sprites");
+        assert_eq!(en.tr_count("sprites", 1), "1 sprite");
+    }
+
+    #[test]
+    fn missing_forms_use_the_picked_language() {
+        let fr = lang("fr");
+
+        // French only has the other form, which beats English's one form
+        assert_eq!(fr.tr_count("batches", 1), "1 lots");
+        assert_eq!(fr.tr_count("batches", 0), "0 lots");
+        assert_eq!(fr.tr_count("sprites", 0), "aucun sprite");
+        assert_eq!(fr.tr_count("sprites", 1), "1 sprites");
+    }
+}
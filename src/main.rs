@@ -7,266 +7,642 @@
 use std::env;
 use std::path;
 use ggez::conf;
-use ggez::mint::Vector2;
 use ggez::timer;
 
 use ggez::{Context, ContextBuilder, GameResult, GameError};
 use ggez::graphics::{self, Color};
 use ggez::event::{self, EventHandler, KeyCode, KeyMods};
+use ggez::input::gamepad::GamepadId;
 use ggez::mint::Point2;
 
-use oorandom::Rand32;
-
 /// *********************************************************************
 /// Import modules.
 /// *********************************************************************
 
+mod animation;
+mod arena;
 mod assets;
+mod audio;
+mod background;
+mod camera;
+mod data;
 mod entity;
+mod events;
+mod files;
 mod settings;
 mod gui;
+mod juice;
+mod lang;
+mod packs;
+mod particles;
+mod reload;
+mod render;
+mod score;
+mod sprite;
+mod text;
+mod tilemap;
+mod validate;
+mod viewport;
+mod wave;
+mod world;
+use arena::*;
 use assets::*;
+use audio::*;
+use background::*;
+use camera::*;
+use data::*;
 use entity::*;
+use files::*;
 use settings::*;
 use gui::*;
+use juice::*;
+use lang::*;
+use packs::*;
+use particles::*;
+use reload::*;
+use render::*;
+use score::*;
+use text::*;
+use tilemap::*;
+use validate::*;
+use viewport::*;
+use wave::*;
+use world::*;
 
 struct MainState {
-    player: Entity,
-    rng: Rand32,
-    monsters: Vec<Entity>,
-    spawn_cycle: f32,
-    ticks: f32,
+    world: World,
+    arenas: Arenas,
     assets: Assets,
-    input: InputState,
-    scale: f32,
-    score: usize,
-    difficulty: u32,
+    audio: Audio,
+    lang: Lang,
+    viewport: Viewport,
     screen: Screen,
     gui: Gui,
+    packs: Packs,
+    batches: Batches,
+    layers: LayerBatches,
+    camera: Camera,
+    particles: Particles,
+    juice: Juice,
+    menu_button: event::MouseButton,
+    show_stats: bool,
+    watcher: Option<Watcher>,
+    reload_error: Option<String>,
 }
 
 impl MainState {
-    pub fn new(ctx: &mut Context, scale: f32) -> GameResult<MainState> {
-        // Load/create resources such as images here.
-        let player = Entity {
-            tag: EntityType::Player,
-            pos: ((152.0 * scale) as i16, (GROUND * scale) as i16),
-            facing: Direction::Left,
-            frame: Frame::Stand,
-            falling: false,
-            jump: 0.0,
-            jump_from: (GROUND * scale) as i16,
-            health: 4,
-            ticks: 0,
-        };
-
+    pub fn new(ctx: &mut Context, packs: Packs, watcher: Option<Watcher>) -> GameResult<MainState> {
         // Seed the RNG
         let mut seed: [u8; 8] = [0; 8];
         getrandom::getrandom(&mut seed[..]).expect("aieee, could not seed rng!");
-        let mut rng = Rand32::new(u64::from_ne_bytes(seed));
 
-        let monsters: Vec<Entity> = Vec::new();
+        // Load assets, noting anything missing instead of stopping
+        let mut report = LoadReport::default();
+        let mut assets = Assets::new(ctx, &mut report)?;
 
-        // Load assets
-        let assets = Assets::new(ctx)?;
+        // Load the wave table
+        let waves = Waves::new_or_default(ctx, &mut report);
 
-        let spawn_cycle = rng.rand_range(4..9) as f32;
+        // Load the arenas with their levels and backgrounds
+        let arenas = Arenas::new(ctx, &mut report);
+        let backgrounds: Vec<String> = arenas.list.iter().flat_map(|a| a.images()).collect();
+        assets.load_backgrounds(ctx, &backgrounds, &mut report)?;
+
+        let world = World::new(waves, arenas.current().clone(), u64::from_ne_bytes(seed));
+
+        // Load sounds and start the music
+        let muted = match fetch_setting_or("mute", SType::Int(0)) {
+            SType::Int(t) => t != 0,
+            _ => false,
+        };
+        let tracks: Vec<String> = arenas.list.iter().map(|a| a.music.clone()).collect();
+        let mut audio = Audio::new(ctx, Volume::from_settings(), muted, &tracks, &mut report);
+        audio.play_music(ctx, &world.arena.music)?;
 
-        let play = Button {pos: (144 * scale as i16, 104 * scale as i16), width: 32, scale, hover: false};
+        // Load the strings in the language picked in settings.cfg
+        let lang = Lang::new(ctx, &mut report);
 
-        let gui = Gui {play};
+        // Tell the player about everything that failed to load at once
+        report.show();
+
+        let juice = Juice::new(JuiceSettings::from_settings(), u64::from_ne_bytes(seed).wrapping_add(2));
+
+        let gui = Gui {
+            title: title_menu(&assets.play, &lang),
+            arenas: arenas_menu(&arenas, &lang),
+            packs: packs_menu(&packs, &lang),
+            sound: sound_menu(&audio, &lang),
+        };
+
+        let batches = Batches::new(ctx, &assets)?;
+        let layers = LayerBatches::new(&world.arena.layers, &assets);
+        let viewport = Viewport::new(ctx, Scaling::from_settings())?;
 
         let s = MainState {
-            player,
-            rng,
+            world,
+            arenas,
             assets,
-            monsters,
-            spawn_cycle,
-            ticks: 0.0,
-            input: InputState::default(),
-            scale,
-            score: 0,
-            difficulty: 0,
+            audio,
+            lang,
+            viewport,
             screen: Screen::Title,
             gui,
+            packs,
+            batches,
+            layers,
+            camera: Camera::new(),
+            particles: Particles::new(u64::from_ne_bytes(seed).wrapping_add(1)),
+            juice,
+            menu_button: menu_button_from_settings(),
+            show_stats: false,
+            watcher,
+            reload_error: None,
         };
 
         Ok(s)
     }
+
+    /// Move around the current screen's menu, or go back from it.
+    fn navigate(&mut self, ctx: &mut Context, nav: Nav) {
+        let used = match (&self.screen, nav) {
+            (Screen::Arenas | Screen::Packs | Screen::Sound, Nav::Back) => Some("back"),
+            // Choosing an arena from the list plays it straight away
+            (Screen::Arenas, Nav::Confirm) if self.gui.arenas.focus == Some("arenas") => Some("play"),
+            // Choosing a pack from the list turns it on or off
+            (Screen::Packs, Nav::Confirm) if self.gui.packs.focus == Some("packs") => Some("enabled"),
+            _ => match self.gui.menu_mut(&self.screen) {
+                Some(menu) => menu.navigate(nav),
+                None => None,
+            },
+        };
+
+        if let Some(id) = used {
+            self.use_widget(ctx, id);
+        }
+    }
+
+    /// Save the pack order if it changed and go back to the title screen.
+    fn leave_packs(&mut self) {
+        if self.packs.changed {
+            if let Err(e) = self.packs.save() {
+                eprintln!("Could not save resource pack order: {}.", e);
+            }
+        }
+
+        self.open(Screen::Title);
+    }
+
+    /// Save the volumes and go back to the title screen.
+    fn leave_sound(&mut self) {
+        let saved = self.audio.volume.save().and_then(|_| save_setting("mute", &(self.audio.muted as u8).to_string()));
+
+        if let Err(e) = saved {
+            eprintln!("Could not save sound settings: {}.", e);
+        }
+
+        self.open(Screen::Title);
+    }
+
+    /// Set the volumes and mute from the sound menu.
+    fn apply_sound(&mut self) {
+        let menu = &self.gui.sound;
+        let volume = Volume {master: menu.value("master"), music: menu.value("music"), sfx: menu.value("sfx")};
+        let muted = menu.is_on("mute");
+
+        self.audio.set_volume(volume);
+
+        if muted != self.audio.muted {
+            self.audio.set_muted(muted);
+        }
+    }
+
+    /// Build the sound menu again after the sound changes some other way,
+    /// keeping the focus.
+    fn refresh_sound(&mut self) {
+        let focus = self.gui.sound.focus;
+        self.gui.sound = sound_menu(&self.audio, &self.lang);
+        self.gui.sound.focus = focus;
+    }
+
+    /// Build the pack menu again after the packs change, keeping the focus.
+    fn refresh_packs(&mut self) {
+        let focus = self.gui.packs.focus;
+        self.gui.packs = packs_menu(&self.packs, &self.lang);
+        self.gui.packs.focus = focus;
+    }
+
+    /// Go to another screen, building its menu from the current state.
+    fn open(&mut self, screen: Screen) {
+        // A click that started on the old screen can't finish on the new one
+        if let Some(menu) = self.gui.menu_mut(&self.screen) {
+            menu.pressed = None;
+        }
+
+        match screen {
+            Screen::Arenas => self.gui.arenas = arenas_menu(&self.arenas, &self.lang),
+            Screen::Packs => self.gui.packs = packs_menu(&self.packs, &self.lang),
+            Screen::Sound => self.gui.sound = sound_menu(&self.audio, &self.lang),
+            _ => (),
+        }
+
+        self.screen = screen;
+        self.audio.play(Sfx::Click);
+    }
+
+    /// Start a run in the selected arena.
+    fn start_arena(&mut self, ctx: &mut Context) {
+        self.world.set_arena(self.arenas.current().clone());
+        self.layers = LayerBatches::new(&self.world.arena.layers, &self.assets);
+        self.particles.clear();
+        self.juice.clear();
+        self.camera.snap(self.world.player_center(), self.world.arena.map.size());
+        self.screen = Screen::Game;
+        self.audio.play(Sfx::Click);
+
+        if let Err(e) = self.audio.play_music(ctx, &self.world.arena.music) {
+            eprintln!("Could not play music: {}.", e);
+        }
+    }
+
+    /// React to a widget in the current screen's menu being pressed or
+    /// changed.
+    fn use_widget(&mut self, ctx: &mut Context, id: &'static str) {
+        match (&self.screen, id) {
+            (Screen::Title | Screen::Death, "play") => self.open(Screen::Arenas),
+            (Screen::Title | Screen::Death, "packs") => self.open(Screen::Packs),
+            (Screen::Title | Screen::Death, "sound") => self.open(Screen::Sound),
+
+            (Screen::Arenas, "arenas") => self.arenas.selected = self.gui.arenas.selected("arenas"),
+            (Screen::Arenas, "play") => self.start_arena(ctx),
+            (Screen::Arenas, "back") => self.open(Screen::Title),
+
+            (Screen::Packs, "packs") => {
+                self.packs.selected = self.gui.packs.selected("packs");
+                self.refresh_packs();
+            }
+            (Screen::Packs, "enabled") => {
+                self.packs.toggle();
+                self.refresh_packs();
+            }
+            (Screen::Packs, "raise") => {
+                self.packs.raise();
+                self.refresh_packs();
+            }
+            (Screen::Packs, "lower") => {
+                self.packs.lower();
+                self.refresh_packs();
+            }
+            (Screen::Packs, "back") => self.leave_packs(),
+
+            (Screen::Sound, "master" | "music" | "sfx" | "mute") => self.apply_sound(),
+            (Screen::Sound, "back") => self.leave_sound(),
+
+            _ => (),
+        }
+    }
+
+    /// Reload any assets or data files that changed on disk. Errors are
+    /// shown on screen instead of stopping the game.
+    fn hot_reload(&mut self, ctx: &mut Context) {
+        let changed = match &mut self.watcher {
+            Some(watcher) => watcher.poll(),
+            None => return,
+        };
+
+        if changed.is_empty() {
+            return;
+        }
+
+        let mut result = self.assets.reload(ctx, &changed);
+        result = result.and(self.batches.set_images(ctx, &self.assets));
+
+        if changed.iter().any(|c| c.starts_with("/lang/")) {
+            let mut report = LoadReport::default();
+            self.lang.reload(ctx, &mut report);
+
+            if ! report.failures.is_empty() {
+                result = result.and(Err(GameError::ResourceLoadError(report.failures.join(", "))));
+            }
+        }
+
+        self.gui.title = title_menu(&self.assets.play, &self.lang);
+        self.refresh_packs();
+        self.refresh_sound();
+
+        if changed.iter().any(|c| c == "/waves.cfg") {
+            match Waves::new(ctx) {
+                Ok(t) => self.world.waves.replace_table(t),
+                Err(e) => result = result.and(Err(e)),
+            }
+        }
+
+        match self.arenas.reload(ctx, &changed) {
+            // Keep playing the current run with the new version of its arena
+            Ok(_) => {
+                if let Some(arena) = self.arenas.list.iter().find(|a| a.id == self.world.arena.id) {
+                    self.world.arena = arena.clone();
+                }
+
+                self.gui.arenas = arenas_menu(&self.arenas, &self.lang);
+
+                // Load the backgrounds of any arenas that were just added
+                let mut report = LoadReport::default();
+                let backgrounds: Vec<String> = self.arenas.list.iter().flat_map(|a| a.images()).collect();
+                result = result.and(self.assets.load_backgrounds(ctx, &backgrounds, &mut report));
+
+                if ! report.failures.is_empty() {
+                    result = result.and(Err(GameError::ResourceLoadError(report.failures.join(", "))));
+                }
+            }
+            Err(e) => result = result.and(Err(e)),
+        }
+
+        // The arena's layers or their images may have changed
+        self.layers = LayerBatches::new(&self.world.arena.layers, &self.assets);
+
+        self.reload_error = match result {
+            Ok(_) => None,
+            Err(e) => Some(e.to_string()),
+        };
+    }
 }
 
 impl EventHandler<GameError> for MainState {
     fn update(&mut self, ctx: &mut Context) -> GameResult {
         const DESIRED_FPS: u32 = 60;
+
+        self.hot_reload(ctx);
+        // Everything holds still for a moment after a stomp
+        let frozen = self.juice.update();
+
         // Update code here...
         while timer::check_update_time(ctx, DESIRED_FPS) {
-            if self.player.falling {
-                self.player.jump += PLAYER_JUMP_TIME;
-            }
-            
-            if self.screen == Screen::Game {
-                handle_player_input(&mut self.player, &mut self.input, self.scale);
+            if ! frozen {
+                self.world.update_player(self.screen == Screen::Game);
             }
         }
 
-        if self.screen == Screen::Game || self.screen == Screen::Death {
-
-            self.ticks += 1.0;
-
-            if (self.ticks / 60.0) == self.spawn_cycle {
-                if self.score % 5 == 0 {
-                    self.difficulty += 1;
-                }
+        if ! frozen {
+            let spawning = self.screen == Screen::Game || self.screen == Screen::Death;
+            self.world.update(spawning);
+            self.particles.update();
+        }
 
-                let difficulty = self.rng.rand_range(2 + self.difficulty..5 + self.difficulty);
-                spawn_monsters(&mut self.rng, &mut self.monsters, difficulty);
-                self.spawn_cycle = self.rng.rand_range(4..9) as f32;
-                self.ticks = 0.0;
-            }
+        // Let the sounds and effects react to what just happened
+        for event in self.world.events.drain() {
+            self.audio.handle(&event);
+            self.particles.handle(&event);
+            self.juice.handle(&event);
         }
 
-        update_monsters(&mut self.monsters, self.scale);
+        // Keep the player in view
+        self.camera.follow(self.world.player_center(), self.world.arena.map.size());
 
-        let mut alive_monsters = Vec::new();
+        if self.world.dead && self.screen == Screen::Game {
+            self.screen = Screen::Death;
 
-        for monster in self.monsters.clone() {
-            if is_touching(&self.player, &monster, self.scale) {
-                if ! self.player.falling {
-                    alive_monsters.push(monster);
-                    if self.player.health > 0 {
-                        self.player.health -= 1;
-                    } else if self.player.health <= 0 {
-                        self.screen = Screen::Death;
-                    }
-                } else if self.player.falling {
-                    self.score += self.rng.rand_range(1..4) as usize;
-                    self.player.jump = 0.0;
-                    self.player.jump_from = self.player.pos.1;
+            if self.arenas.scores.submit(&self.world.arena.id, self.world.score) {
+                if let Err(e) = self.arenas.scores.save() {
+                    eprintln!("Could not save high scores: {}.", e);
                 }
-            } else {
-                alive_monsters.push(monster);
             }
         }
 
-        self.monsters = alive_monsters;
+        if ! frozen {
+            animate_entity(&mut self.world.player, &self.assets);
+            for monster in self.world.monsters.iter_mut() {
+                animate_entity(monster, &self.assets);
+            }
+        }
+
+        self.audio.flush(ctx)?;
 
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        // Draw at the screen's own size, then scale it up to the window
+        self.viewport.begin(ctx)?;
         graphics::clear(ctx, Color::WHITE);
 
-        // Draw the player
-        draw_entity(&mut self.assets, ctx, &self.player, self.player.pos, self.scale)?;
+        if self.screen == Screen::Arenas || self.screen == Screen::Packs || self.screen == Screen::Sound {
+            if let Some(menu) = self.gui.menu_mut(&self.screen) {
+                draw_menu(ctx, menu, self.assets.font)?;
+            }
+            return self.viewport.present(ctx);
+        }
 
-        // Draw the monsters
-        draw_monsters(&mut self.monsters, &mut self.assets, ctx, self.scale)?;
+        // Draw the arena's background layers behind everything else
+        let view = Point2 {x: self.camera.pos.x + self.juice.offset.x, y: self.camera.pos.y + self.juice.offset.y};
+        self.batches.stats = DrawStats::default();
+        draw_layers(ctx, &self.world.arena.layers, &mut self.layers, &self.assets, view, &mut self.batches.stats)?;
 
-        // Draw the ground
-        draw_ground(&mut self.assets, ctx, self.scale)?;
+        // Draw the level, the player and the monsters, one batch per image,
+        // then the score popups above them, all as seen by the camera
+        self.camera.apply(ctx, self.juice.offset)?;
+
+        self.batches.add_tiles(&self.world.arena.map);
+        self.batches.add_entity(&self.assets, &self.world.player);
+        for monster in &self.world.monsters {
+            self.batches.add_entity(&self.assets, monster);
+        }
+        if self.juice.flash_alpha() > 0.0 {
+            self.batches.add_flash(&self.assets, &self.world.player, self.juice.flash_alpha());
+        }
+        self.batches.add_particles(&self.particles);
+        self.batches.draw(ctx)?;
+
+        draw_popups(ctx, &self.world.popups, self.assets.font)?;
+
+        self.camera.reset(ctx)?;
 
         // Draw code here...
-        let score_str = format!("{}", self.score);
-        let score_len = score_str.chars().count() as f32 / 2.0;
-        let score_dest = Point2 {x: (SCREEN_WIDTH * self.scale) / 2.0 - score_len * 8.0 * self.scale, y: 0.0};
-        let score_display = graphics::Text::new((score_str, self.assets.font, 16.0 * self.scale));
-        graphics::draw(ctx, &score_display, (score_dest, 0.0, Color::from_rgb(120, 147, 65)))?;
+        let top = Point2 {x: SCREEN_WIDTH / 2.0, y: 0.0};
+        let banner = Point2 {x: SCREEN_WIDTH / 2.0, y: SCREEN_HEIGHT / 2.0 - 64.0};
+        let dark = Color::from_rgb(90, 117, 35);
+        let light = Color::from_rgb(120, 147, 65);
+        let shadow = Color::from_rgb(200, 214, 170);
+
+        let score_style = TextStyle::new(self.assets.font, 16.0, light).align(graphics::Align::Center);
+        draw_text(ctx, &format!("{}", self.world.score), top, &score_style)?;
+
+        if self.world.combo.multiplier() > 1 {
+            let combo_style = TextStyle::new(self.assets.font, 8.0, dark).align(graphics::Align::Center);
+            draw_text(ctx, &self.lang.tr_args("combo", &[("n", &self.world.combo.multiplier())]), Point2 {x: top.x, y: 16.0}, &combo_style)?;
+        }
 
-        if self.player.health == 0 {
-            let game_over_str = "game over";
-            let game_over_len = game_over_str.chars().count() as f32 / 2.0;
-            let game_over_dest = Point2 {x: (SCREEN_WIDTH * self.scale) / 2.0 - game_over_len * 16.0 * self.scale, y: (SCREEN_HEIGHT * self.scale) / 2.0 - 64.0 * self.scale};
-            let game_over_display = graphics::Text::new((game_over_str, self.assets.font, 32.0 * self.scale));
-            graphics::draw(ctx, &game_over_display, (game_over_dest, 0.0, Color::from_rgb(90, 117, 35)))?;
+        let banner_style = TextStyle::new(self.assets.font, 32.0, dark).align(graphics::Align::Center).shadow(shadow);
+
+        if self.world.waves.banner > 0 && self.screen == Screen::Game {
+            draw_text(ctx, &self.lang.tr_args("wave", &[("n", &self.world.waves.number)]), banner, &banner_style)?;
+        }
+
+        if self.world.player.health == 0 {
+            draw_text(ctx, self.lang.tr("game_over"), banner, &banner_style)?;
         }
 
         if self.screen == Screen::Title || self.screen == Screen::Death {
-            Button::draw(ctx, &mut self.gui.play, &mut self.assets.play)?;
+            draw_menu(ctx, &mut self.gui.title, self.assets.font)?;
         }
 
-        graphics::present(ctx)?;
+        if let Some(error) = &self.reload_error {
+            let error_style = TextStyle::new(self.assets.font, 8.0, Color::from_rgb(200, 40, 40)).outline(Color::WHITE);
+            let bounds = graphics::Rect::new(4.0, 4.0, SCREEN_WIDTH - 8.0, SCREEN_HEIGHT - 8.0);
+            draw_wrapped(ctx, &self.lang.tr_args("reload_failed", &[("error", error)]), bounds, &error_style)?;
+        }
+
+        if self.show_stats {
+            draw_stats(ctx, &self.batches.stats, self.assets.font, &self.lang)?;
+        }
+
+        self.viewport.present(ctx)?;
 
         timer::yield_now();
 
         Ok(())
     }
 
-    fn key_down_event(&mut self, _ctx: &mut Context, keycode: KeyCode, keymods: KeyMods, _repeat: bool) {
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods, _repeat: bool) {
+        // Fullscreen can be toggled from anywhere
+        if keycode == KeyCode::F11 {
+            if let Err(e) = self.viewport.toggle_fullscreen(ctx) {
+                eprintln!("Could not change fullscreen mode: {}.", e);
+            }
+
+            return;
+        }
+
+        // Shift and up or down moves the selected pack
+        if self.screen == Screen::Packs && keymods.contains(KeyMods::SHIFT) {
+            match keycode {
+                KeyCode::Up => return self.use_widget(ctx, "raise"),
+                KeyCode::Down => return self.use_widget(ctx, "lower"),
+                _ => (),
+            }
+        }
+
+        // Menus are driven the same way by the keyboard and a gamepad
+        if self.screen != Screen::Game {
+            if let Some(nav) = Nav::from_key(keycode, keymods) {
+                self.navigate(ctx, nav);
+                return;
+            }
+        }
+
         match keycode {
             KeyCode::Left => {
                 if keymods.contains(KeyMods::SHIFT) {
-                    self.input.x = -2.5;
+                    self.world.input.x = -2.5;
                 } else {
-                    self.input.x = -1.0;
+                    self.world.input.x = -1.0;
                 }
 
-                self.player.facing = Direction::Left;
+                self.world.player.facing = Direction::Left;
             }
 
             KeyCode::Right => {
                 if keymods.contains(KeyMods::SHIFT) {
-                    self.input.x = 2.5;
+                    self.world.input.x = 2.5;
                 } else {
-                    self.input.x = 1.0;
+                    self.world.input.x = 1.0;
                 }
                 
-                self.player.facing = Direction::Right;
+                self.world.player.facing = Direction::Right;
             }
 
             KeyCode::LShift => {
-                if self.input.x == 1.0 {
-                    self.input.x = 2.5;
-                } else if self.input.x == -1.0 {
-                    self.input.x = -2.5
+                if self.world.input.x == 1.0 {
+                    self.world.input.x = 2.5;
+                } else if self.world.input.x == -1.0 {
+                    self.world.input.x = -2.5
                 }
             }
 
             KeyCode::Z => {
-                if ! self.player.falling {
-                    self.input.jump = true;
-                    self.player.jump_from = self.player.pos.1;
+                if ! self.world.player.falling {
+                    self.world.input.jump = true;
+                    self.world.player.jump_from = self.world.player.pos.1;
+                }
+            }
+
+            KeyCode::X => self.world.input.attack = true,
+            KeyCode::M => {
+                self.audio.toggle_mute();
+                self.refresh_sound();
+            }
+            KeyCode::F3 => self.show_stats = ! self.show_stats,
+
+            KeyCode::P => {
+                if self.screen == Screen::Title || self.screen == Screen::Death {
+                    self.open(Screen::Packs);
                 }
             }
 
-            KeyCode::X => self.input.attack = true,
             _ => (),
         }
     }
 
     fn key_up_event(&mut self, _ctx: &mut Context, _keycode: KeyCode, _keymods: KeyMods) {
         match _keycode {
-            KeyCode::Left | KeyCode::Right => self.input.x = 0.0,
-            KeyCode::Z => self.input.jump = false,
-            KeyCode::X => self.input.attack = false,
+            KeyCode::Left | KeyCode::Right => self.world.input.x = 0.0,
+            KeyCode::Z => self.world.input.jump = false,
+            KeyCode::X => self.world.input.attack = false,
             _ => (),
         }
     }
 
-    fn mouse_button_down_event(&mut self, _ctx: &mut Context, _button: event::MouseButton, _x: f32, _y: f32) {
-        if self.screen == Screen::Title || self.screen == Screen::Death {
-            if self.gui.play.hover {
-                self.screen = Screen::Game;
-                self.monsters.clear();
-                self.player.health = 4;
-                self.score = 0;
-                self.difficulty = 0;
-                self.ticks = 0.0;
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) {
+        self.viewport.resize(width, height);
+    }
+
+    fn gamepad_button_down_event(&mut self, ctx: &mut Context, button: event::Button, _id: GamepadId) {
+        if self.screen != Screen::Game {
+            if let Some(nav) = Nav::from_button(button) {
+                self.navigate(ctx, nav);
             }
         }
     }
 
-    fn mouse_button_up_event(&mut self, _ctx: &mut Context, _button: event::MouseButton, _x: f32, _y: f32) {}
+    // Only the current screen's menu sees the mouse, and only the menu
+    // button uses it
+    fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: event::MouseButton, x: f32, y: f32) {
+        if button != self.menu_button {
+            return;
+        }
+
+        let pos = self.viewport.to_screen(x, y);
+
+        if let Some(menu) = self.gui.menu_mut(&self.screen) {
+            menu.press(pos.x, pos.y);
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, ctx: &mut Context, button: event::MouseButton, x: f32, y: f32) {
+        if button != self.menu_button {
+            return;
+        }
+
+        let pos = self.viewport.to_screen(x, y);
+        let used = self.gui.menu_mut(&self.screen).and_then(|m| m.release(pos.x, pos.y));
+
+        if let Some(id) = used {
+            self.use_widget(ctx, id);
+        }
+    }
 
     fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
-        Button::hover(&mut self.gui.play, x, y, self.scale);
+        let pos = self.viewport.to_screen(x, y);
+
+        if let Some(menu) = self.gui.menu_mut(&self.screen) {
+            if menu.hover(pos.x, pos.y) {
+                self.audio.play(Sfx::Hover);
+            }
+        }
     }
 }
 
 fn main() -> GameResult {
-    let scale = match fetch_setting("scale", &SType::Int(1)) {
-        SType::Int(t) => t as f32,
-        _ => -1.0,
-    };
+    let args: Vec<String> = env::args().collect();
 
     // Add CARGO_MANIFEST_DIR/resources to resource paths
     let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
@@ -277,10 +653,86 @@ fn main() -> GameResult {
         path::PathBuf::from("./resources")
     };
 
-    // Make a Context.
-    let (mut ctx, event_loop) = ContextBuilder::new("game", "AVS Origami")
+    // Check a resource directory for problems without opening a window
+    if args.len() > 1 && args[1] == "--validate" {
+        let root = match args.get(2) {
+            Some(t) => path::PathBuf::from(t),
+            None => resource_dir,
+        };
+
+        if ! Validation::run(&root).print() {
+            std::process::exit(1);
+        }
+
+        return Ok(());
+    }
+
+    let scale = match fetch_setting("scale", &SType::Int(1)) {
+        SType::Int(t) => t as f32,
+        _ => -1.0,
+    };
+
+    // Find resource packs in the mods directory
+    let packs = Packs::discover(path::Path::new("./mods"));
+
+    // Run the simulation without a window or audio device
+    if args.len() > 1 && args[1] == "--headless" {
+        let ticks = match args.get(2) {
+            Some(t) => t.parse::<u32>().unwrap_or(3600),
+            None => 3600,
+        };
+
+        let mut seed: [u8; 8] = [0; 8];
+        getrandom::getrandom(&mut seed[..]).expect("aieee, could not seed rng!");
+
+        let bytes = read_disk_file(&packs.locate(&resource_dir, "waves.cfg"), WAVES_FILE)?;
+        let waves = Waves::from_records(&parse_records(WAVES_FILE, &String::from_utf8_lossy(&bytes))?)?;
+        // Play the arena given after the tick count, or the first one
+        let bytes = read_disk_file(&packs.locate(&resource_dir, "arenas.cfg"), ARENAS_FILE)?;
+        let arenas = arenas_from_records(&parse_records(ARENAS_FILE, &String::from_utf8_lossy(&bytes))?)?;
+
+        let mut arena = match args.get(3) {
+            Some(id) => match arenas.into_iter().find(|a| &a.id == id) {
+                Some(t) => t,
+                None => return Err(GameError::ResourceLoadError(format!("no arena called '{}'", id))),
+            },
+            None => arenas.into_iter().next().unwrap(),
+        };
+
+        let bytes = read_disk_file(&packs.locate(&resource_dir, arena.level.trim_start_matches('/')), &arena.level)?;
+        arena.map = Tilemap::from_records(&parse_records(&arena.level, &String::from_utf8_lossy(&bytes))?)?;
+
+        let mut world = World::new(waves, arena, u64::from_ne_bytes(seed));
+        let stats = run_headless(&mut world, ticks);
+
+        println!("reached wave {} with a score of {} ({} stomps, best stomp {}, {} hits taken)", stats.wave, stats.score, stats.stomps, stats.best_stomp, stats.hits);
+        return Ok(());
+    }
+
+    // In development mode, watch the resource directories for changes
+    let watcher = if args.iter().any(|a| a == "--dev") {
+        let mut dirs = packs.resource_paths();
+        dirs.push(resource_dir.clone());
+        Some(Watcher::new(dirs))
+    } else {
+        None
+    };
+
+    // Make a Context. Enabled packs are searched before the bundled
+    // resources, so their files win.
+    let mut builder = ContextBuilder::new("game", "AVS Origami")
         .window_setup(conf::WindowSetup::default().title("game"))
-        .window_mode(conf::WindowMode::default().dimensions(SCREEN_WIDTH * scale, SCREEN_HEIGHT * scale))
+        .window_mode(conf::WindowMode::default()
+            .dimensions(SCREEN_WIDTH * scale, SCREEN_HEIGHT * scale)
+            .min_dimensions(SCREEN_WIDTH, SCREEN_HEIGHT)
+            .resizable(true)
+            .fullscreen_type(fullscreen_from_settings()));
+
+    for pack_dir in packs.resource_paths() {
+        builder = builder.add_resource_path(pack_dir);
+    }
+
+    let (mut ctx, event_loop) = builder
         .add_resource_path(resource_dir)
         .build()
         .expect("aieee, could not create ggez context!");
@@ -288,21 +740,8 @@ fn main() -> GameResult {
     // Create an instance of your event handler.
     // Usually, you should provide it with the Context object to
     // use when setting your game up.
-    let game = MainState::new(&mut ctx, scale)?;
+    let game = MainState::new(&mut ctx, packs, watcher)?;
 
     // Run!
     event::run(ctx, event_loop, game)
 }
-
-fn draw_ground(assets: &mut Assets, ctx: &mut Context, scale: f32) -> GameResult {
-    let mut pos = Point2 {x: 0.0, y: GROUND * scale + 16.0 * scale};
-    let image = &mut assets.moss;
-
-    Ok (
-        for _ in 0..(SCREEN_WIDTH * scale / 8.0) as i32 {
-            let drawparams = graphics::DrawParam::new().dest(pos).scale(Vector2{x: scale, y: scale});
-            graphics::draw(ctx, image, drawparams)?;
-            pos.x += 8.0;
-        }
-    )
-}
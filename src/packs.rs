@@ -0,0 +1,196 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ggez::GameResult;
+
+use crate::data::*;
+use crate::gui::*;
+use crate::lang::*;
+
+/// The file each pack describes itself with.
+const MANIFEST: &str = "pack.cfg";
+
+/// The file the load order is saved to, inside the mods directory.
+const ORDER: &str = "packs.cfg";
+
+/// *********************************************************************
+/// Define a struct for a resource pack: a directory laid out like
+/// resources/ whose files replace the bundled ones with the same path.
+/// *********************************************************************
+
+pub struct Pack {
+    pub dir: String,
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    pub enabled: bool,
+}
+
+impl Pack {
+    fn load(mods_dir: &Path, dir: &str) -> GameResult<Pack> {
+        let records = read_records(&mods_dir.join(dir).join(MANIFEST))?;
+
+        let record = match records.iter().find(|r| r.kind == "pack") {
+            Some(t) => t,
+            None => return Err(ggez::GameError::ResourceLoadError(format!("{}/{}: no [pack] record", dir, MANIFEST))),
+        };
+
+        Ok (
+            Pack {
+                dir: dir.to_owned(),
+                name: record.string("name")?,
+                version: record.string("version")?,
+                author: record.string("author")?,
+                enabled: true,
+            }
+        )
+    }
+}
+
+/// *********************************************************************
+/// Define a struct holding every pack in the mods directory, highest
+/// priority first, along with the pack selected in the pack menu.
+/// *********************************************************************
+
+pub struct Packs {
+    pub mods_dir: PathBuf,
+    pub list: Vec<Pack>,
+    pub selected: usize,
+    pub changed: bool,
+}
+
+impl Packs {
+    /// Find every pack in `mods_dir` and put them in the saved order. Packs
+    /// that are not in the saved order yet go at the end, enabled.
+    pub fn discover(mods_dir: &Path) -> Packs {
+        let mut found = Vec::new();
+
+        if let Ok(entries) = fs::read_dir(mods_dir) {
+            for entry in entries.flatten() {
+                if entry.path().join(MANIFEST).is_file() {
+                    let dir = entry.file_name().to_string_lossy().into_owned();
+
+                    match Pack::load(mods_dir, &dir) {
+                        Ok(t) => found.push(t),
+                        Err(e) => eprintln!("Skipping resource pack '{}': {}.", dir, e),
+                    }
+                }
+            }
+        }
+
+        found.sort_by(|a, b| a.dir.cmp(&b.dir));
+
+        let mut list = Vec::new();
+
+        if let Ok(records) = read_records(&mods_dir.join(ORDER)) {
+            for record in records.iter().filter(|r| r.kind == "pack") {
+                let dir = match record.get("dir") {
+                    Some(t) => t,
+                    None => continue,
+                };
+
+                if let Some(i) = found.iter().position(|p| p.dir == dir) {
+                    let mut pack = found.remove(i);
+                    pack.enabled = record.get("enabled") != Some("0");
+                    list.push(pack);
+                }
+            }
+        }
+
+        list.append(&mut found);
+
+        Packs {mods_dir: mods_dir.to_path_buf(), list, selected: 0, changed: false}
+    }
+
+    /// Directories of the enabled packs, highest priority first.
+    pub fn resource_paths(&self) -> Vec<PathBuf> {
+        self.list.iter().filter(|p| p.enabled).map(|p| self.mods_dir.join(&p.dir)).collect()
+    }
+
+    /// Find a resource file on disk the same way ggez would, checking the
+    /// enabled packs before the bundled resources.
+    pub fn locate(&self, bundled: &Path, file: &str) -> PathBuf {
+        for dir in self.resource_paths() {
+            if dir.join(file).is_file() {
+                return dir.join(file);
+            }
+        }
+
+        bundled.join(file)
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(pack) = self.list.get_mut(self.selected) {
+            pack.enabled = ! pack.enabled;
+            self.changed = true;
+        }
+    }
+
+    /// Give the selected pack a higher priority.
+    pub fn raise(&mut self) {
+        if self.selected > 0 && self.selected < self.list.len() {
+            self.list.swap(self.selected, self.selected - 1);
+            self.selected -= 1;
+            self.changed = true;
+        }
+    }
+
+    /// Give the selected pack a lower priority.
+    pub fn lower(&mut self) {
+        if self.selected + 1 < self.list.len() {
+            self.list.swap(self.selected, self.selected + 1);
+            self.selected += 1;
+            self.changed = true;
+        }
+    }
+
+    /// Write the load order to the mods directory.
+    pub fn save(&self) -> io::Result<()> {
+        let mut text = String::from("# resource pack load order, written by the game.\n# packs nearer the top win when two packs contain the same file.\n");
+
+        for pack in &self.list {
+            text.push_str(&format!("\n[pack]\ndir = {}\nenabled = {}\n", pack.dir, pack.enabled as u8));
+        }
+
+        fs::create_dir_all(&self.mods_dir)?;
+        fs::write(self.mods_dir.join(ORDER), text)
+    }
+}
+
+/// *********************************************************************
+/// Create a function to build the resource pack menu, listing each pack
+/// with whether it's on. The bundled resources are listed last, since
+/// every pack comes before them, and can't be turned off or moved.
+/// *********************************************************************
+
+pub fn packs_menu(packs: &Packs, lang: &Lang) -> Menu {
+    let mut items: Vec<String> = packs.list.iter().map(|p| lang.tr_args("pack", &[("name", &p.name), ("version", &p.version), ("author", &p.author)])).collect();
+    let mut values: Vec<String> = packs.list.iter().map(|p| lang.tr(if p.enabled { "pack_on" } else { "pack_off" }).to_owned()).collect();
+
+    items.push(lang.tr("bundled_resources").to_owned());
+    values.push(lang.tr("pack_on").to_owned());
+
+    let enabled = packs.list.get(packs.selected).map(|p| p.enabled).unwrap_or(true);
+
+    let buttons = Widget::stack(Axis::Horizontal, Align::Start, 16.0, vec![
+        Widget::toggle("enabled", lang.tr("pack_enabled"), enabled),
+        Widget::text_button("raise", lang.tr("pack_raise")),
+        Widget::text_button("lower", lang.tr("pack_lower")),
+        Widget::text_button("back", lang.tr("back")),
+    ]);
+
+    let root = Widget::stack(Axis::Vertical, Align::Start, 12.0, vec![
+        Widget::title(lang.tr("resource_packs")),
+        Widget::list("packs", items, packs.selected).values(values),
+        buttons,
+    ]);
+
+    let help = if packs.changed {
+        lang.tr("packs_restart")
+    } else {
+        lang.tr("packs_help")
+    };
+
+    Menu::new(root, (Align::Start, Align::Start)).panel(Widget::label(help), (Align::Start, Align::End)).with_focus("packs")
+}
@@ -0,0 +1,240 @@
+use ggez::graphics::Color;
+use oorandom::Rand32;
+
+use crate::entity::*;
+use crate::events::*;
+
+/// The most particles alive at once. Anything emitted past this is
+/// dropped instead of growing the pool.
+const MAX_PARTICLES: usize = 256;
+
+/// *********************************************************************
+/// Define a struct describing a burst of particles: how many, how long
+/// they live, how fast and in which directions they fly, and what they
+/// look like. Angles are in degrees, with 0 pointing right and -90
+/// pointing up. Speeds are in pixels per update.
+/// *********************************************************************
+
+pub struct Emitter {
+    pub count: u32,
+    pub lifetime: (u32, u32),
+    pub speed: (f32, f32),
+    pub angle: (f32, f32),
+    pub gravity: f32,
+    pub size: f32,
+    pub color: Color,
+    pub fade: bool,
+}
+
+/// A puff of dust where a monster was stomped.
+pub const STOMP_PUFF: Emitter = Emitter {
+    count: 10,
+    lifetime: (12, 20),
+    speed: (0.4, 1.2),
+    angle: (-180.0, 0.0),
+    gravity: 0.0,
+    size: 2.0,
+    color: Color {r: 0.47, g: 0.58, b: 0.25, a: 1.0},
+    fade: true,
+};
+
+/// Bits of bone thrown up when a skeleton is stomped.
+pub const BONE_SHARDS: Emitter = Emitter {
+    count: 6,
+    lifetime: (30, 45),
+    speed: (1.0, 2.0),
+    angle: (-150.0, -30.0),
+    gravity: 0.12,
+    size: 2.0,
+    color: Color {r: 0.93, g: 0.91, b: 0.82, a: 1.0},
+    fade: false,
+};
+
+/// Sparks where the player is hurt or dies.
+pub const HIT_SPARKS: Emitter = Emitter {
+    count: 6,
+    lifetime: (8, 14),
+    speed: (1.0, 1.8),
+    angle: (-180.0, 180.0),
+    gravity: 0.0,
+    size: 1.0,
+    color: Color {r: 0.78, g: 0.16, b: 0.16, a: 1.0},
+    fade: true,
+};
+
+/// Dust kicked up to both sides when the player lands.
+pub const LANDING_DUST: Emitter = Emitter {
+    count: 6,
+    lifetime: (10, 16),
+    speed: (0.3, 0.8),
+    angle: (-170.0, -10.0),
+    gravity: 0.02,
+    size: 1.0,
+    color: Color {r: 0.35, g: 0.46, b: 0.14, a: 1.0},
+    fade: true,
+};
+
+/// Sparkles where a monster appears.
+pub const SPAWN_SPARKLES: Emitter = Emitter {
+    count: 8,
+    lifetime: (20, 30),
+    speed: (0.2, 0.6),
+    angle: (-180.0, 180.0),
+    gravity: -0.01,
+    size: 1.0,
+    color: Color {r: 1.0, g: 0.95, b: 0.6, a: 1.0},
+    fade: true,
+};
+
+/// *********************************************************************
+/// Define a struct for one particle. Positions are in pixels.
+/// *********************************************************************
+
+#[derive(Clone, Copy)]
+pub struct Particle {
+    pub pos: (f32, f32),
+    pub vel: (f32, f32),
+    pub gravity: f32,
+    pub size: f32,
+    pub color: Color,
+    pub fade: bool,
+    pub life: u32,
+    pub lifetime: u32,
+}
+
+impl Particle {
+    /// How opaque the particle is now, fading out over its life if it
+    /// fades at all.
+    pub fn alpha(&self) -> f32 {
+        if self.fade {
+            self.color.a * self.life as f32 / self.lifetime as f32
+        } else {
+            self.color.a
+        }
+    }
+}
+
+const DEAD: Particle = Particle {
+    pos: (0.0, 0.0),
+    vel: (0.0, 0.0),
+    gravity: 0.0,
+    size: 0.0,
+    color: Color {r: 0.0, g: 0.0, b: 0.0, a: 0.0},
+    fade: false,
+    life: 0,
+    lifetime: 1,
+};
+
+/// *********************************************************************
+/// Define a struct holding every particle in a pool that is allocated
+/// once. The live particles are kept at the front of the pool, so dead
+/// ones are replaced by swapping in the last live one. Particles are only
+/// made from the world's events and have their own random numbers, so
+/// they never change how the game plays out, and a headless run simply
+/// doesn't have any.
+/// *********************************************************************
+
+pub struct Particles {
+    pool: Vec<Particle>,
+    live: usize,
+    rng: Rand32,
+}
+
+impl Particles {
+    pub fn new(seed: u64) -> Particles {
+        Particles {pool: vec![DEAD; MAX_PARTICLES], live: 0, rng: Rand32::new(seed)}
+    }
+
+    /// The particles that are alive.
+    pub fn live(&self) -> &[Particle] {
+        &self.pool[..self.live]
+    }
+
+    pub fn clear(&mut self) {
+        self.live = 0;
+    }
+
+    /// Emit the particles for something that happened in the world.
+    pub fn handle(&mut self, event: &Event) {
+        match event {
+            Event::MonsterSpawned {pos} => self.emit(&SPAWN_SPARKLES, center(*pos)),
+            Event::MonsterStomped {tag, pos, ..} => {
+                self.emit(&STOMP_PUFF, center(*pos));
+
+                if *tag == EntityType::Skeleton {
+                    self.emit(&BONE_SHARDS, center(*pos));
+                }
+            }
+            Event::PlayerLanded {pos} => self.emit(&LANDING_DUST, feet(*pos)),
+            Event::PlayerDamaged {pos} | Event::PlayerDied {pos} => self.emit(&HIT_SPARKS, center(*pos)),
+            _ => (),
+        }
+    }
+
+    /// Emit a burst of particles from `at`.
+    pub fn emit(&mut self, emitter: &Emitter, at: (f32, f32)) {
+        for _ in 0..emitter.count {
+            if self.live == MAX_PARTICLES {
+                return;
+            }
+
+            let angle = self.range(emitter.angle).to_radians();
+            let speed = self.range(emitter.speed);
+            let lifetime = self.rng.rand_range(emitter.lifetime.0..emitter.lifetime.1 + 1);
+
+            self.pool[self.live] = Particle {
+                pos: at,
+                vel: (angle.cos() * speed, angle.sin() * speed),
+                gravity: emitter.gravity,
+                size: emitter.size,
+                color: emitter.color,
+                fade: emitter.fade,
+                life: lifetime,
+                lifetime,
+            };
+
+            self.live += 1;
+        }
+    }
+
+    /// Move every particle by one update and drop the ones that died.
+    pub fn update(&mut self) {
+        let mut i = 0;
+
+        while i < self.live {
+            let particle = &mut self.pool[i];
+
+            particle.life -= 1;
+
+            if particle.life == 0 {
+                self.live -= 1;
+                self.pool.swap(i, self.live);
+                continue;
+            }
+
+            particle.vel.1 += particle.gravity;
+            particle.pos.0 += particle.vel.0;
+            particle.pos.1 += particle.vel.1;
+
+            i += 1;
+        }
+    }
+
+    fn range(&mut self, range: (f32, f32)) -> f32 {
+        range.0 + (range.1 - range.0) * self.rng.rand_float()
+    }
+}
+
+/// *********************************************************************
+/// Helper functions.
+/// *********************************************************************
+
+/// The middle of an entity at `pos`, in pixels.
+fn center(pos: (i16, i16)) -> (f32, f32) {
+    (pos.0 as f32 + 8.0, pos.1 as f32 + 8.0)
+}
+
+/// The middle of the bottom edge of an entity at `pos`, in pixels.
+fn feet(pos: (i16, i16)) -> (f32, f32) {
+    (pos.0 as f32 + 8.0, pos.1 as f32 + 16.0)
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// How often the resource directories are checked for changes, in ticks.
+const POLL_TICKS: u32 = 30;
+
+/// *********************************************************************
+/// Define a struct that watches the resource directories for changed
+/// files, by checking their modification times every so often.
+/// *********************************************************************
+
+pub struct Watcher {
+    dirs: Vec<PathBuf>,
+    stamps: HashMap<PathBuf, SystemTime>,
+    ticks: u32,
+}
+
+impl Watcher {
+    pub fn new(dirs: Vec<PathBuf>) -> Watcher {
+        let mut watcher = Watcher {dirs, stamps: HashMap::new(), ticks: 0};
+        watcher.scan();
+        watcher
+    }
+
+    /// Returns the resource paths (like "/sprites/player.png") of every file
+    /// that changed since the last poll. Only actually looks every
+    /// POLL_TICKS calls.
+    pub fn poll(&mut self) -> Vec<String> {
+        self.ticks += 1;
+
+        if self.ticks < POLL_TICKS {
+            return Vec::new();
+        }
+
+        self.ticks = 0;
+        self.scan()
+    }
+
+    fn scan(&mut self) -> Vec<String> {
+        let mut changed = Vec::new();
+
+        for dir in self.dirs.clone() {
+            let mut files = Vec::new();
+            list_files(&dir, &mut files);
+
+            for file in files {
+                let modified = match fs::metadata(&file).and_then(|m| m.modified()) {
+                    Ok(t) => t,
+                    Err(_) => continue,
+                };
+
+                if self.stamps.insert(file.clone(), modified) != Some(modified) {
+                    changed.push(resource_path(&dir, &file));
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+/// *********************************************************************
+/// Helper functions.
+/// *********************************************************************
+
+pub fn list_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+
+            if path.is_dir() {
+                list_files(&path, files);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+}
+
+/// Turn a file inside `root` into the path ggez would load it by.
+pub fn resource_path(root: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    let parts: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().into_owned()).collect();
+
+    format!("/{}", parts.join("/"))
+}
@@ -0,0 +1,247 @@
+use ggez::graphics::{self, spritebatch::SpriteBatch, Color, DrawParam, FilterMode, Image};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+
+use crate::assets::*;
+use crate::entity::*;
+use crate::lang::*;
+use crate::particles::*;
+use crate::text::*;
+use crate::tilemap::*;
+
+/// *********************************************************************
+/// Define a struct counting the sprite batches drawn in the last frame
+/// and the sprites in them. Text and menus are drawn on their own and
+/// aren't counted.
+/// *********************************************************************
+
+#[derive(Default)]
+pub struct DrawStats {
+    pub batches: u32,
+    pub sprites: u32,
+}
+
+impl DrawStats {
+    /// Count a batch of `sprites` sprites.
+    pub fn add(&mut self, sprites: u32) {
+        self.batches += 1;
+        self.sprites += sprites;
+    }
+}
+
+/// *********************************************************************
+/// Create an enumeration of the sprite batches, in the order they're
+/// drawn.
+/// *********************************************************************
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BatchKind {
+    Ground,
+    Grass,
+    Moss,
+    Player,
+    Zombie,
+    Skeleton,
+    Flash,
+    Particles,
+}
+
+pub const BATCH_KINDS: [BatchKind; 8] = [
+    BatchKind::Ground,
+    BatchKind::Grass,
+    BatchKind::Moss,
+    BatchKind::Player,
+    BatchKind::Zombie,
+    BatchKind::Skeleton,
+    BatchKind::Flash,
+    BatchKind::Particles,
+];
+
+/// *********************************************************************
+/// Define a struct holding one sprite batch per image. Sprites are added
+/// to the batch for their image through the frame and each batch is
+/// drawn with a single draw call, so the number of draw calls stays the
+/// same however many monsters there are. Tiles are drawn first, so they
+/// are behind the entities, and particles last, in front of them. The
+/// flash batch draws the player as a white silhouette over itself.
+/// *********************************************************************
+
+pub struct Batches {
+    pub ground: SpriteBatch,
+    pub grass: SpriteBatch,
+    pub moss: SpriteBatch,
+    pub player: SpriteBatch,
+    pub zombie: SpriteBatch,
+    pub skeleton: SpriteBatch,
+    pub flash: SpriteBatch,
+    pub particles: SpriteBatch,
+    counts: [u32; BATCH_KINDS.len()],
+    pub stats: DrawStats,
+}
+
+impl Batches {
+    pub fn new(ctx: &mut Context, assets: &Assets) -> GameResult<Batches> {
+        // Particles are a single white pixel, stretched and tinted
+        let pixel = Image::solid(ctx, 1, Color::WHITE)?;
+
+        Ok (
+            Batches {
+                ground: new_batch(&assets.ground),
+                grass: new_batch(&assets.grass),
+                moss: new_batch(&assets.moss),
+                player: new_batch(&assets.player.image),
+                zombie: new_batch(&assets.zombie.image),
+                skeleton: new_batch(&assets.skeleton.image),
+                flash: new_batch(&silhouette(ctx, &assets.player.image)?),
+                particles: new_batch(&pixel),
+                counts: [0; BATCH_KINDS.len()],
+                stats: DrawStats::default(),
+            }
+        )
+    }
+
+    /// Point every batch at the current images, after assets are reloaded.
+    pub fn set_images(&mut self, ctx: &mut Context, assets: &Assets) -> GameResult {
+        self.ground.set_image(assets.ground.clone());
+        self.grass.set_image(assets.grass.clone());
+        self.moss.set_image(assets.moss.clone());
+        self.player.set_image(assets.player.image.clone());
+        self.zombie.set_image(assets.zombie.image.clone());
+        self.skeleton.set_image(assets.skeleton.image.clone());
+        self.flash.set_image(silhouette(ctx, &assets.player.image)?);
+
+        Ok(())
+    }
+
+    pub fn add_entity(&mut self, assets: &Assets, entity: &Entity) {
+        let sheet = assets.sheet(&entity.tag);
+        let dest = pos_to_p2(entity.pos);
+
+        // Sprites are drawn facing left, so mirror them when facing right
+        let mirror = match entity.facing {
+            Direction::Left => false,
+            Direction::Right => true,
+        };
+
+        if let Some(param) = sheet.param(entity.anim.clip.name(), entity.anim.frame, dest, mirror) {
+            let kind = match entity.tag {
+                EntityType::Player => BatchKind::Player,
+                EntityType::Zombie => BatchKind::Zombie,
+                EntityType::Skeleton => BatchKind::Skeleton,
+            };
+
+            self.add(kind, param);
+        }
+    }
+
+    /// Add every tile in the level.
+    pub fn add_tiles(&mut self, map: &Tilemap) {
+        for (i, cell) in map.grid.iter().enumerate() {
+            let tile = match cell {
+                Some(t) => &map.tiles[*t],
+                None => continue,
+            };
+
+            let kind = match tile.image.as_str() {
+                GROUND_IMAGE => BatchKind::Ground,
+                GRASS_IMAGE => BatchKind::Grass,
+                _ => BatchKind::Moss,
+            };
+
+            let dest = Point2 {x: (i % map.width) as f32 * TILE_SIZE, y: (i / map.width) as f32 * TILE_SIZE};
+            self.add(kind, DrawParam::new().dest(dest));
+        }
+    }
+
+    /// Add the player as a white silhouette, `alpha` opaque.
+    pub fn add_flash(&mut self, assets: &Assets, player: &Entity, alpha: f32) {
+        let mirror = match player.facing {
+            Direction::Left => false,
+            Direction::Right => true,
+        };
+
+        if let Some(param) = assets.player.param(player.anim.clip.name(), player.anim.frame, pos_to_p2(player.pos), mirror) {
+            self.add(BatchKind::Flash, param.color(Color::new(1.0, 1.0, 1.0, alpha)));
+        }
+    }
+
+    /// Add every live particle.
+    pub fn add_particles(&mut self, particles: &Particles) {
+        for particle in particles.live() {
+            let dest = Point2 {x: particle.pos.0.round(), y: particle.pos.1.round()};
+            let color = Color::new(particle.color.r, particle.color.g, particle.color.b, particle.alpha());
+            let size = Vector2 {x: particle.size, y: particle.size};
+
+            self.add(BatchKind::Particles, DrawParam::new().dest(dest).scale(size).color(color));
+        }
+    }
+
+    /// Draw every batch that has sprites in it and empty them, adding what
+    /// was drawn to the stats.
+    pub fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        for kind in BATCH_KINDS {
+            let count = self.counts[kind as usize];
+
+            if count > 0 {
+                let batch = self.batch_mut(kind);
+                graphics::draw(ctx, batch, DrawParam::new())?;
+                batch.clear();
+
+                self.stats.add(count);
+                self.counts[kind as usize] = 0;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn batch_mut(&mut self, kind: BatchKind) -> &mut SpriteBatch {
+        match kind {
+            BatchKind::Ground => &mut self.ground,
+            BatchKind::Grass => &mut self.grass,
+            BatchKind::Moss => &mut self.moss,
+            BatchKind::Player => &mut self.player,
+            BatchKind::Zombie => &mut self.zombie,
+            BatchKind::Skeleton => &mut self.skeleton,
+            BatchKind::Flash => &mut self.flash,
+            BatchKind::Particles => &mut self.particles,
+        }
+    }
+
+    fn add(&mut self, kind: BatchKind, param: DrawParam) {
+        self.batch_mut(kind).add(param);
+        self.counts[kind as usize] += 1;
+    }
+}
+
+/// A copy of an image with every visible pixel turned white.
+fn silhouette(ctx: &mut Context, image: &Image) -> GameResult<Image> {
+    let mut pixels = image.to_rgba8(ctx)?;
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel[0] = 255;
+        pixel[1] = 255;
+        pixel[2] = 255;
+    }
+
+    Image::from_rgba8(ctx, image.width(), image.height(), &pixels)
+}
+
+pub fn new_batch(image: &Image) -> SpriteBatch {
+    let mut batch = SpriteBatch::new(image.clone());
+    batch.set_filter(FilterMode::Nearest);
+    batch
+}
+
+/// *********************************************************************
+/// Create a function to draw the debug counter in the top right corner.
+/// *********************************************************************
+
+pub fn draw_stats(ctx: &mut Context, stats: &DrawStats, font: graphics::Font, lang: &Lang) -> GameResult {
+    let batches = lang.tr_count("batches", stats.batches as usize);
+    let sprites = lang.tr_count("sprites", stats.sprites as usize);
+    let stats_str = lang.tr_args("stats", &[("batches", &batches), ("sprites", &sprites)]);
+    let style = TextStyle::new(font, 8.0, Color::from_rgb(200, 40, 40)).align(graphics::Align::Right);
+
+    draw_text(ctx, &stats_str, Point2 {x: SCREEN_WIDTH - 4.0, y: 4.0}, &style)
+}
@@ -0,0 +1,179 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Color};
+use ggez::mint::Point2;
+
+use crate::data::*;
+use crate::entity::*;
+use crate::events::*;
+use crate::text::*;
+
+/// The highest multiplier a combo can reach.
+pub const MAX_MULTIPLIER: usize = 8;
+
+/// How long a score popup stays on screen, in ticks.
+const POPUP_TICKS: u32 = 45;
+
+/// The file the best score in each arena is saved to, next to settings.cfg.
+const SCORES_FILE: &str = "./scores.cfg";
+
+/// *********************************************************************
+/// Define a struct counting consecutive stomps made without touching
+/// the ground.
+/// *********************************************************************
+
+#[derive(Default)]
+pub struct Combo {
+    pub count: usize,
+}
+
+impl Combo {
+    /// The multiplier the next stomp will be scored with.
+    pub fn multiplier(&self) -> usize {
+        (self.count + 1).min(MAX_MULTIPLIER)
+    }
+
+    /// Score a stomp on `monster` and extend the combo. Returns the points
+    /// earned.
+    pub fn stomp(&mut self, monster: &Entity) -> usize {
+        let points = monster.tag.points() * self.multiplier();
+        self.count += 1;
+        points
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+/// *********************************************************************
+/// Define a struct totting up how a run went from the world's events.
+/// *********************************************************************
+
+#[derive(Default)]
+pub struct RunStats {
+    pub wave: u32,
+    pub score: usize,
+    pub stomps: u32,
+    pub best_stomp: usize,
+    pub hits: u32,
+}
+
+impl RunStats {
+    pub fn handle(&mut self, event: &Event) {
+        match event {
+            Event::WaveStarted {number} => self.wave = *number,
+            Event::ScoreChanged {score} => self.score = *score,
+            Event::MonsterStomped {points, ..} => {
+                self.stomps += 1;
+                self.best_stomp = self.best_stomp.max(*points);
+            }
+            Event::PlayerDamaged {..} => self.hits += 1,
+            _ => (),
+        }
+    }
+}
+
+/// *********************************************************************
+/// Define a struct for the points that float up from a stomped monster.
+/// *********************************************************************
+
+pub struct Popup {
+    pub pos: (i16, i16),
+    pub points: usize,
+    pub ticks: u32,
+}
+
+impl Popup {
+    pub fn new(pos: (i16, i16), points: usize) -> Popup {
+        Popup {pos, points, ticks: 0}
+    }
+}
+
+/// *********************************************************************
+/// Create a function to move popups upwards and remove expired ones.
+/// *********************************************************************
+
+pub fn update_popups(popups: &mut Vec<Popup>) {
+    for popup in popups.iter_mut() {
+        popup.ticks += 1;
+
+        if popup.ticks % 2 == 0 {
+            popup.pos = (popup.pos.0, popup.pos.1 - 1);
+        }
+    }
+
+    popups.retain(|popup| popup.ticks < POPUP_TICKS);
+}
+
+/// *********************************************************************
+/// Create a function to draw popups, fading them out as they expire.
+/// *********************************************************************
+
+pub fn draw_popups(ctx: &mut Context, popups: &[Popup], font: graphics::Font) -> GameResult {
+    for popup in popups {
+        let alpha = 1.0 - popup.ticks as f32 / POPUP_TICKS as f32;
+        let dest = Point2 {x: popup.pos.0 as f32, y: popup.pos.1 as f32};
+        let style = TextStyle::new(font, 8.0, Color::new(0.35, 0.46, 0.14, alpha));
+        draw_text(ctx, &format!("{}", popup.points), dest, &style)?;
+    }
+
+    Ok(())
+}
+
+/// *********************************************************************
+/// Define a struct holding the best score reached in each arena.
+/// *********************************************************************
+
+#[derive(Default)]
+pub struct HighScores {
+    pub best: Vec<(String, usize)>,
+}
+
+impl HighScores {
+    /// Read the saved scores. A missing or broken file means no scores yet.
+    pub fn load() -> HighScores {
+        let mut scores = HighScores::default();
+
+        if let Ok(records) = read_records(Path::new(SCORES_FILE)) {
+            for record in records.iter().filter(|r| r.kind == "score") {
+                if let (Ok(arena), Ok(best)) = (record.string("arena"), record.int("best")) {
+                    scores.best.push((arena, best as usize));
+                }
+            }
+        }
+
+        scores
+    }
+
+    pub fn best(&self, arena: &str) -> usize {
+        self.best.iter().find(|(a, _)| a == arena).map(|(_, b)| *b).unwrap_or(0)
+    }
+
+    /// Record the score of a finished run. Returns true if it's a new best.
+    pub fn submit(&mut self, arena: &str, score: usize) -> bool {
+        if score <= self.best(arena) {
+            return false;
+        }
+
+        match self.best.iter_mut().find(|(a, _)| a == arena) {
+            Some(entry) => entry.1 = score,
+            None => self.best.push((arena.to_owned(), score)),
+        }
+
+        true
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let mut text = String::from("# best score in each arena, written by the game.\n");
+
+        for (arena, best) in &self.best {
+            text.push_str(&format!("\n[score]\narena = {}\nbest = {}\n", arena, best));
+        }
+
+        fs::write(SCORES_FILE, text)
+    }
+}
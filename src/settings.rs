@@ -1,4 +1,5 @@
 use std::fs;
+use std::io;
 use std::process;
 
 use std::error::Error;
@@ -11,44 +12,57 @@ use native_dialog::{MessageDialog, MessageType};
     String(S),
 }
 
-fn get_setting(setting: &str, s_type: &SType<f32, i32, String>) -> Result<SType<f32, i32, String>, Box<dyn Error>> {
-    let settings_file = fs::read_to_string(String::from("settings.cfg"))?;
+/// A setting's value as read from settings.cfg.
+pub type Setting = SType<f32, i32, String>;
 
-    let mut result_str = String::new();
-    let mut result_found = false;
+/// Find a setting in settings.cfg and parse its value as the same type
+/// as `s_type`. Gives back None if the setting isn't there, and an error
+/// if the file can't be read or the value doesn't parse.
+fn get_setting(setting: &str, s_type: &Setting) -> Result<Option<Setting>, Box<dyn Error>> {
+    let settings_file = fs::read_to_string(String::from("settings.cfg"))?;
 
     for line in settings_file.lines() {
-        if line.contains(setting) {
-            for c in line.chars() {
-                if c == '#' {
-                    break;
-                } else if result_found {
-                    result_str.push(c);
-                } else if c == '=' {
-                    result_found = true;
-                }
-            }
+        // Everything after a '#' is a comment
+        let line = line.split('#').next().unwrap_or("");
 
-            if result_str.len() > 0 {
-                result_str.retain(|c| !c.is_whitespace());
+        let (key, value) = match line.split_once('=') {
+            Some(t) => t,
+            None => continue,
+        };
 
-                let result = match s_type {
-                    SType::Float(_) => SType::Float(result_str.parse::<f32>().unwrap()),
-                    SType::Int(_) => SType::Int(result_str.parse::<i32>().unwrap()),
-                    SType::String(_) => SType::String(result_str),
-                };
+        if key.trim() != setting {
+            continue;
+        }
 
-                
-                return Ok(result);
-            }
+        let mut result_str = value.to_owned();
+        result_str.retain(|c| !c.is_whitespace());
+
+        if result_str.is_empty() {
+            continue;
         }
+
+        let bad_value = |e: &dyn Error| format!("'{}' is not a valid value for {} ({})", result_str, setting, e);
+
+        let result = match s_type {
+            SType::Float(_) => SType::Float(result_str.parse::<f32>().map_err(|e| bad_value(&e))?),
+            SType::Int(_) => SType::Int(result_str.parse::<i32>().map_err(|e| bad_value(&e))?),
+            SType::String(_) => SType::String(result_str),
+        };
+
+        return Ok(Some(result));
     }
 
-    return Err("could not locate setting ".to_owned() + setting)?;
+    Ok(None)
 }
 
 pub fn fetch_setting(setting: &str, s_type: &SType<f32, i32, String>) -> SType<f32, i32, String> {
-    match get_setting(setting, s_type) {
+    let result = match get_setting(setting, s_type) {
+        Ok(Some(t)) => Ok(t),
+        Ok(None) => Err(format!("could not locate setting {}", setting).into()),
+        Err(e) => Err(e),
+    };
+
+    match result {
         Ok(t) => return t,
         Err(e) => {
             let _ = MessageDialog::new().set_title("game").set_text(&format!("Error while loading settings: {}.", e)).set_type(MessageType::Error).show_alert();
@@ -56,4 +70,49 @@ pub fn fetch_setting(setting: &str, s_type: &SType<f32, i32, String>) -> SType<f
             process::exit(1)
         }
     }
-}
\ No newline at end of file
+}
+
+/// Like `fetch_setting`, but gives back `default` when the setting isn't
+/// in settings.cfg, so a settings file from before the setting was added
+/// still works. A value that can't be read also gives back `default`,
+/// after saying what was wrong with it.
+pub fn fetch_setting_or(setting: &str, default: SType<f32, i32, String>) -> SType<f32, i32, String> {
+    match get_setting(setting, &default) {
+        Ok(Some(t)) => t,
+        Ok(None) => default,
+        Err(e) => {
+            eprintln!("Error while loading settings, using the default for {}: {}.", setting, e);
+            default
+        }
+    }
+}
+
+/// Write a setting's value to settings.cfg, keeping the comment after it.
+/// Settings that aren't in the file yet go on the end of it.
+pub fn save_setting(setting: &str, value: &str) -> io::Result<()> {
+    let settings_file = fs::read_to_string(String::from("settings.cfg"))?;
+    let mut lines: Vec<String> = Vec::new();
+    let mut found = false;
+
+    for line in settings_file.lines() {
+        let (content, comment) = match line.find('#') {
+            Some(n) => line.split_at(n),
+            None => (line, ""),
+        };
+
+        match content.split_once('=') {
+            Some((key, _)) if ! found && key.trim() == setting => {
+                let comment = if comment.is_empty() { String::new() } else { format!(" {}", comment) };
+                lines.push(format!("{} = {}{}", setting, value, comment));
+                found = true;
+            }
+            _ => lines.push(line.to_owned()),
+        }
+    }
+
+    if ! found {
+        lines.push(format!("{} = {}", setting, value));
+    }
+
+    fs::write("settings.cfg", lines.join("\n") + "\n")
+}
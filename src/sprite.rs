@@ -0,0 +1,152 @@
+use ggez::graphics::{self, DrawParam, FilterMode, Rect};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+
+use crate::data::*;
+use crate::files::*;
+
+/// *********************************************************************
+/// Define a struct for a named rectangle cut out of a sprite sheet, in
+/// pixels. The pivot is the column the frame is mirrored around.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct SheetFrame {
+    pub name: String,
+    pub rect: Rect,
+    pub pivot: f32,
+}
+
+/// *********************************************************************
+/// Define a struct for a named sequence of frames.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct Animation {
+    pub name: String,
+    pub frames: Vec<usize>,
+    pub duration: u32,
+    pub looping: bool,
+    pub flip: bool,
+}
+
+/// *********************************************************************
+/// Define a struct holding everything a sheet descriptor says, without
+/// the image itself.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct SheetDef {
+    pub image: String,
+    pub frames: Vec<SheetFrame>,
+    pub animations: Vec<Animation>,
+}
+
+impl SheetDef {
+    pub fn from_records(records: &[Record]) -> GameResult<SheetDef> {
+        let mut image = None;
+        let mut frames: Vec<SheetFrame> = Vec::new();
+        let mut animations = Vec::new();
+
+        for record in records {
+            match record.kind.as_str() {
+                "sheet" => image = Some(record.string("image")?),
+
+                "frame" => {
+                    let rect = Rect::new(
+                        record.int("x")? as f32,
+                        record.int("y")? as f32,
+                        record.int("w")? as f32,
+                        record.int("h")? as f32,
+                    );
+                    let pivot = record.float_or("pivot", rect.w / 2.0)?;
+                    frames.push(SheetFrame {name: record.string("name")?, rect, pivot});
+                }
+
+                "animation" => {
+                    let mut indices = Vec::new();
+
+                    for name in record.string("frames")?.split(',') {
+                        match frames.iter().position(|f| f.name == name.trim()) {
+                            Some(i) => indices.push(i),
+                            None => return Err(record.error(&format!("no frame named '{}'", name.trim()))),
+                        }
+                    }
+
+                    let looping = match record.get("mode").unwrap_or("loop") {
+                        "loop" => true,
+                        "once" => false,
+                        t => return Err(record.error(&format!("mode must be 'loop' or 'once', not '{}'", t))),
+                    };
+
+                    animations.push(Animation {
+                        name: record.string("name")?,
+                        frames: indices,
+                        duration: record.int_or("duration", 1)?.max(1),
+                        looping,
+                        flip: record.int_or("flip", 0)? != 0,
+                    });
+                }
+
+                _ => return Err(record.error("unknown record type")),
+            }
+        }
+
+        match image {
+            Some(image) => Ok(SheetDef {image, frames, animations}),
+            None => Err(ggez::GameError::ResourceLoadError("sprite sheet has no [sheet] record".to_owned())),
+        }
+    }
+
+    pub fn animation(&self, name: &str) -> Option<&Animation> {
+        self.animations.iter().find(|a| a.name == name)
+    }
+}
+
+/// *********************************************************************
+/// Define a struct for a sprite sheet: one image holding every frame of
+/// a character, drawn a frame at a time using source rectangles.
+/// *********************************************************************
+
+pub struct SpriteSheet {
+    pub path: String,
+    pub def: SheetDef,
+    pub image: graphics::Image,
+}
+
+impl SpriteSheet {
+    pub fn new(ctx: &mut Context, path: &str) -> GameResult<SpriteSheet> {
+        let def = SheetDef::from_records(&load_records(ctx, path)?)?;
+
+        let bytes = read_file(ctx, &def.image)?;
+        let mut image = graphics::Image::from_bytes(ctx, &bytes)?;
+        image.set_filter(FilterMode::Nearest);
+
+        Ok(SpriteSheet {path: path.to_owned(), def, image})
+    }
+
+    /// The source rectangle of a frame, as a fraction of the sheet.
+    pub fn src(&self, frame: usize) -> Rect {
+        let rect = self.def.frames[frame].rect;
+        let w = self.image.width() as f32;
+        let h = self.image.height() as f32;
+
+        Rect::new(rect.x / w, rect.y / h, rect.w / w, rect.h / h)
+    }
+
+    /// The draw parameters for frame `index` of the named animation with
+    /// its top left corner at `dest`, mirrored around the frame's pivot if
+    /// `mirror` is set.
+    pub fn param(&self, animation: &str, index: usize, dest: Point2<f32>, mirror: bool) -> Option<DrawParam> {
+        let animation = self.def.animation(animation)?;
+
+        let frame = animation.frames[index % animation.frames.len()];
+        let pivot = self.def.frames[frame].pivot;
+
+        if animation.flip != mirror {
+            Some(DrawParam::new().src(self.src(frame)).dest(Point2 {x: dest.x + 2.0 * pivot, y: dest.y}).scale(Vector2 {x: -1.0, y: 1.0}))
+        } else {
+            Some(DrawParam::new().src(self.src(frame)).dest(dest))
+        }
+    }
+}
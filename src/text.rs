@@ -0,0 +1,159 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Align, Color, Font, Rect};
+use ggez::mint::Point2;
+
+/// *********************************************************************
+/// Create an enumeration of what's drawn behind text to keep it readable
+/// over busy backgrounds. Both are a pixel wide.
+/// *********************************************************************
+
+#[derive(Clone, Copy)]
+pub enum Effect {
+    Plain,
+    Shadow(Color),
+    Outline(Color),
+}
+
+/// *********************************************************************
+/// Define a struct for how a piece of text looks. Sizes are rounded to
+/// whole pixels so the font stays sharp, and `align` says which edge of
+/// the text the position it's drawn at is.
+/// *********************************************************************
+
+#[derive(Clone, Copy)]
+pub struct TextStyle {
+    pub font: Font,
+    pub size: f32,
+    pub color: Color,
+    pub align: Align,
+    pub effect: Effect,
+}
+
+impl TextStyle {
+    pub fn new(font: Font, size: f32, color: Color) -> TextStyle {
+        TextStyle {font, size: size.round().max(1.0), color, align: Align::Left, effect: Effect::Plain}
+    }
+
+    pub fn align(mut self, align: Align) -> TextStyle {
+        self.align = align;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> TextStyle {
+        self.color = color;
+        self
+    }
+
+    pub fn shadow(mut self, color: Color) -> TextStyle {
+        self.effect = Effect::Shadow(color);
+        self
+    }
+
+    pub fn outline(mut self, color: Color) -> TextStyle {
+        self.effect = Effect::Outline(color);
+        self
+    }
+}
+
+/// *********************************************************************
+/// Create functions to measure text and break it into lines.
+/// *********************************************************************
+
+/// How wide and tall `text` is when drawn in `style`, from the font's
+/// glyphs.
+pub fn measure(ctx: &Context, text: &str, style: &TextStyle) -> (f32, f32) {
+    let rect = graphics::Text::new((text, style.font, style.size)).dimensions(ctx);
+    (rect.w, rect.h.max(style.size))
+}
+
+/// Break `text` into lines no wider than `width`, between words where it
+/// can. Words too long for a line on their own are split between
+/// characters. Line breaks already in the text are kept.
+pub fn wrap(ctx: &Context, text: &str, width: f32, style: &TextStyle) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let joined = if line.is_empty() { word.to_owned() } else { format!("{} {}", line, word) };
+
+            if measure(ctx, &joined, style).0 <= width {
+                line = joined;
+                continue;
+            }
+
+            if ! line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+
+            // Split a word too long for a line of its own
+            for ch in word.chars() {
+                line.push(ch);
+
+                if measure(ctx, &line, style).0 > width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, ch.to_string()));
+                }
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// *********************************************************************
+/// Create functions to draw text, snapped to whole pixels. `pos` is the
+/// top of the text, at its left edge, middle or right edge depending on
+/// the style's alignment.
+/// *********************************************************************
+
+pub fn draw_text(ctx: &mut Context, text: &str, pos: Point2<f32>, style: &TextStyle) -> GameResult {
+    let display = graphics::Text::new((text, style.font, style.size));
+
+    let x = match style.align {
+        Align::Left => pos.x,
+        Align::Center => pos.x - display.dimensions(ctx).w / 2.0,
+        Align::Right => pos.x - display.dimensions(ctx).w,
+    };
+
+    let x = x.round();
+    let y = pos.y.round();
+
+    match style.effect {
+        Effect::Plain => (),
+        Effect::Shadow(color) => graphics::draw(ctx, &display, (Point2 {x: x + 1.0, y: y + 1.0}, 0.0, color))?,
+        Effect::Outline(color) => {
+            for (dx, dy) in [(-1.0, -1.0), (0.0, -1.0), (1.0, -1.0), (-1.0, 0.0), (1.0, 0.0), (-1.0, 1.0), (0.0, 1.0), (1.0, 1.0)] {
+                graphics::draw(ctx, &display, (Point2 {x: x + dx, y: y + dy}, 0.0, color))?;
+            }
+        }
+    }
+
+    graphics::draw(ctx, &display, (Point2 {x, y}, 0.0, style.color))
+}
+
+/// Draw `text` wrapped to fit the width of `bounds`, with each line
+/// aligned inside it. Lines that don't fit the height are left off.
+pub fn draw_wrapped(ctx: &mut Context, text: &str, bounds: Rect, style: &TextStyle) -> GameResult {
+    let x = match style.align {
+        Align::Left => bounds.x,
+        Align::Center => bounds.x + bounds.w / 2.0,
+        Align::Right => bounds.x + bounds.w,
+    };
+
+    let mut y = bounds.y;
+
+    for line in wrap(ctx, text, bounds.w, style) {
+        if y + style.size > bounds.y + bounds.h {
+            break;
+        }
+
+        draw_text(ctx, &line, Point2 {x, y}, style)?;
+        y += style.size;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,375 @@
+use ggez::{Context, GameResult};
+
+use crate::assets::*;
+use crate::data::*;
+
+/// The width and height of a tile, in pixels.
+pub const TILE_SIZE: f32 = 8.0;
+
+/// The size of an entity's hitbox, in pixels.
+const ENTITY_SIZE: f32 = 16.0;
+
+/// *********************************************************************
+/// Create an enumeration of how entities collide with a tile.
+/// *********************************************************************
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TileKind {
+    /// Blocks movement from every side.
+    Solid,
+    /// Can be stood on, but jumped up through from below.
+    OneWay,
+    /// Drawn, but doesn't collide with anything.
+    Decorative,
+}
+
+/// *********************************************************************
+/// Define a struct describing one type of tile in a level.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct TileDef {
+    pub symbol: char,
+    pub image: String,
+    pub kind: TileKind,
+}
+
+impl TileDef {
+    fn from_record(record: &Record) -> GameResult<TileDef> {
+        let symbol = record.string("symbol")?;
+        let mut chars = symbol.chars();
+
+        let symbol = match (chars.next(), chars.next()) {
+            (Some(t), None) if t != '.' => t,
+            _ => return Err(record.error(&format!("'{}' is not a single character other than '.'", symbol))),
+        };
+
+        let image = record.string("image")?;
+
+        if ! TILE_IMAGES.contains(&image.as_str()) {
+            return Err(record.error(&format!("'{}' is not a tile image", image)));
+        }
+
+        let kind = match record.string("kind")?.as_str() {
+            "solid" => TileKind::Solid,
+            "oneway" => TileKind::OneWay,
+            "decorative" => TileKind::Decorative,
+            other => return Err(record.error(&format!("'{}' is not solid, oneway or decorative", other))),
+        };
+
+        Ok(TileDef {symbol, image, kind})
+    }
+}
+
+/// *********************************************************************
+/// Create an enumeration of what a moving entity bumped into.
+/// *********************************************************************
+
+#[derive(PartialEq)]
+pub enum Contact {
+    None,
+    Floor,
+    Ceiling,
+}
+
+/// *********************************************************************
+/// Define a struct for a level: a grid of tiles with its top left
+/// corner at the top left of the world.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct Tilemap {
+    pub tiles: Vec<TileDef>,
+    pub width: usize,
+    pub height: usize,
+    pub grid: Vec<Option<usize>>,
+}
+
+impl Tilemap {
+    pub fn new(ctx: &mut Context, path: &str) -> GameResult<Tilemap> {
+        Tilemap::from_records(&load_records(ctx, path)?)
+    }
+
+    /// Load a level, falling back to a flat floor of moss if it can't be
+    /// loaded.
+    pub fn new_or_default(ctx: &mut Context, path: &str, report: &mut LoadReport) -> Tilemap {
+        match Tilemap::new(ctx, path) {
+            Ok(t) => t,
+            Err(e) => {
+                report.add(path, &e);
+                Tilemap::flat()
+            }
+        }
+    }
+
+    /// A single row of moss along the bottom of the screen.
+    pub fn flat() -> Tilemap {
+        let width = 40;
+        let height = 30;
+        let mut grid = vec![None; width * height];
+
+        for cell in grid.iter_mut().skip(width * (height - 1)) {
+            *cell = Some(0);
+        }
+
+        let moss = TileDef {symbol: 'm', image: MOSS_IMAGE.to_owned(), kind: TileKind::Solid};
+
+        Tilemap {tiles: vec![moss], width, height, grid}
+    }
+
+    pub fn from_records(records: &[Record]) -> GameResult<Tilemap> {
+        let mut tiles: Vec<TileDef> = Vec::new();
+        let mut rows = Vec::new();
+
+        for record in records {
+            match record.kind.as_str() {
+                "tile" => {
+                    let tile = TileDef::from_record(record)?;
+
+                    if tiles.iter().any(|t| t.symbol == tile.symbol) {
+                        return Err(record.error(&format!("tile '{}' is defined twice", tile.symbol)));
+                    }
+
+                    tiles.push(tile);
+                }
+
+                "map" => {
+                    for (key, value) in &record.fields {
+                        if key != "row" {
+                            return Err(record.error(&format!("unknown field '{}'", key)));
+                        }
+
+                        rows.push((record, value.clone()));
+                    }
+                }
+
+                _ => return Err(record.error("unknown record type")),
+            }
+        }
+
+        let width = match rows.first() {
+            Some((record, row)) if row.is_empty() => return Err(record.error("rows can't be empty")),
+            Some((_, row)) => row.chars().count(),
+            None => return Err(ggez::GameError::ResourceLoadError("level has no [map] rows".to_owned())),
+        };
+
+        let height = rows.len();
+        let mut grid = Vec::with_capacity(width * height);
+
+        for (record, row) in &rows {
+            if row.chars().count() != width {
+                return Err(record.error("every row has to be the same length"));
+            }
+
+            for symbol in row.chars() {
+                if symbol == '.' {
+                    grid.push(None);
+                } else {
+                    match tiles.iter().position(|t| t.symbol == symbol) {
+                        Some(i) => grid.push(Some(i)),
+                        None => return Err(record.error(&format!("no [tile] with symbol '{}'", symbol))),
+                    }
+                }
+            }
+        }
+
+        Ok(Tilemap {tiles, width, height, grid})
+    }
+
+    /// The width and height of the level, in pixels.
+    pub fn size(&self) -> (f32, f32) {
+        (self.width as f32 * TILE_SIZE, self.height as f32 * TILE_SIZE)
+    }
+
+    /// The tile at a column and row. Everything below the bottom of the
+    /// map is solid, so nothing can fall out of the level.
+    pub fn kind(&self, col: i32, row: i32) -> Option<TileKind> {
+        if row >= self.height as i32 {
+            return Some(TileKind::Solid);
+        }
+
+        if col < 0 || row < 0 || col >= self.width as i32 {
+            return None;
+        }
+
+        self.grid[row as usize * self.width + col as usize].map(|i| self.tiles[i].kind)
+    }
+
+    /// Whether a solid tile overlaps an entity with its top left corner at
+    /// `pos`.
+    pub fn overlaps_solid(&self, pos: (i16, i16)) -> bool {
+        let (cols, rows) = cells(pos);
+
+        rows.into_iter().any(|row| cols.clone().any(|col| self.kind(col, row) == Some(TileKind::Solid)))
+    }
+
+    /// Whether an entity at `pos` is standing exactly on top of a solid or
+    /// one way tile.
+    pub fn on_floor(&self, pos: (i16, i16)) -> bool {
+        let size = TILE_SIZE as i16;
+        let feet = pos.1 + ENTITY_SIZE as i16;
+
+        if feet.rem_euclid(size) != 0 {
+            return false;
+        }
+
+        let row = feet.div_euclid(size) as i32;
+        let (cols, _) = cells(pos);
+
+        cols.into_iter().any(|col| matches!(self.kind(col, row), Some(TileKind::Solid) | Some(TileKind::OneWay)))
+    }
+
+    /// Move an entity sideways by `dx`, stopping at solid tiles and the
+    /// sides of the map. Returns the new x position and whether it was
+    /// blocked.
+    pub fn move_x(&self, pos: (i16, i16), dx: i16) -> (i16, bool) {
+        let right = (self.size().0 - ENTITY_SIZE) as i16;
+        let mut x = pos.0;
+
+        while x != pos.0 + dx {
+            let next = x + dx.signum();
+
+            if next < 0 || next > right || self.overlaps_solid((next, pos.1)) {
+                return (x, true);
+            }
+
+            x = next;
+        }
+
+        (x, false)
+    }
+
+    /// Move an entity up or down to `y`, landing on the first floor it
+    /// falls onto or stopping under the first ceiling it hits.
+    pub fn move_y(&self, pos: (i16, i16), y: i16) -> (i16, Contact) {
+        let mut current = pos.1;
+
+        while current < y {
+            if self.on_floor((pos.0, current)) {
+                return (current, Contact::Floor);
+            }
+
+            current += 1;
+        }
+
+        while current > y {
+            if self.overlaps_solid((pos.0, current - 1)) {
+                return (current, Contact::Ceiling);
+            }
+
+            current -= 1;
+        }
+
+        if current > pos.1 && self.on_floor((pos.0, current)) {
+            return (current, Contact::Floor);
+        }
+
+        (current, Contact::None)
+    }
+}
+
+/// *********************************************************************
+/// Helper functions.
+/// *********************************************************************
+
+/// The columns and rows of the tiles an entity at `pos` overlaps.
+fn cells(pos: (i16, i16)) -> (std::ops::RangeInclusive<i32>, std::ops::RangeInclusive<i32>) {
+    let size = TILE_SIZE as i16;
+    let entity = ENTITY_SIZE as i16;
+
+    let cols = pos.0.div_euclid(size) as i32..=(pos.0 + entity - 1).div_euclid(size) as i32;
+    let rows = pos.1.div_euclid(size) as i32..=(pos.1 + entity - 1).div_euclid(size) as i32;
+
+    (cols, rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 10 tiles wide and 8 tall. A ceiling at the top left, a one way
+    // platform at row 3, a one tile wall standing on the floor at column
+    // 7, bushes at columns 2 and 3 and a gap in the floor at the right
+    // edge.
+    const LEVEL: &str = "
+[tile]
+symbol = s
+image = /ground.png
+kind = solid
+
+[tile]
+symbol = o
+image = /grass.png
+kind = oneway
+
+[tile]
+symbol = d
+image = /moss.png
+kind = decorative
+
+[map]
+row = ..........
+row = ss........
+row = ..........
+row = ....oo....
+row = ..........
+row = .......s..
+row = ..dd......
+row = ssssssss..
+";
+
+    fn level() -> Tilemap {
+        Tilemap::from_records(&parse_records("test.level", LEVEL).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn solid_walls_block_both_ways() {
+        let map = level();
+
+        assert!(map.move_x((32, 40), 20) == (40, true));
+        assert!(map.move_x((64, 40), -20) == (64, true));
+        assert!(map.overlaps_solid((48, 40)));
+        assert!(map.move_y((0, 40), 0) == (16, Contact::Ceiling));
+    }
+
+    #[test]
+    fn one_way_platforms_only_hold_from_above() {
+        let map = level();
+
+        // Falling onto it lands on top
+        assert!(map.move_y((32, 0), 20) == (8, Contact::Floor));
+        assert!(map.on_floor((32, 8)));
+
+        // Jumping up from below and walking into it go straight through
+        assert!(map.move_y((32, 40), 10) == (10, Contact::None));
+        assert!(map.move_x((8, 16), 32) == (40, false));
+        assert!(! map.overlaps_solid((32, 20)));
+    }
+
+    #[test]
+    fn decorative_tiles_dont_collide() {
+        let map = level();
+
+        assert!(! map.overlaps_solid((16, 40)));
+        assert!(map.move_x((32, 40), -20) == (12, false));
+        assert!(map.move_y((16, 20), 60) == (40, Contact::Floor));
+        assert!(! map.on_floor((16, 32)));
+    }
+
+    #[test]
+    fn map_edges_stop_movement() {
+        let map = level();
+
+        // The sides
+        assert!(map.move_x((4, 24), -10) == (0, true));
+        assert!(map.move_x((60, 0), 10) == (64, true));
+
+        // Falling through the gap lands on the bottom of the map
+        assert!(map.move_y((64, 40), 100) == (48, Contact::Floor));
+        assert!(map.on_floor((64, 48)));
+
+        // Flat levels stand on their bottom row
+        let flat = Tilemap::flat();
+        assert!(flat.move_y((0, 0), 500) == (216, Contact::Floor));
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ab_glyph::{Font, FontRef};
+
+use crate::animation::*;
+use crate::arena::*;
+use crate::assets::*;
+use crate::audio::*;
+use crate::data::*;
+use crate::lang::*;
+use crate::reload::*;
+use crate::sprite::*;
+use crate::tilemap::*;
+use crate::wave::*;
+
+/// The size every character frame is drawn at.
+const FRAME_SIZE: f32 = 16.0;
+
+/// Images that are not part of a sprite sheet, with the size they have to be.
+const IMAGES: [(&str, u32, u32); 4] = [
+    (GROUND_IMAGE, 8, 8),
+    (GRASS_IMAGE, 8, 8),
+    (MOSS_IMAGE, 8, 8),
+    (PLAY_IMAGE, 32, 32),
+];
+
+const SHEETS: [&str; 3] = [PLAYER_SHEET, ZOMBIE_SHEET, SKELETON_SHEET];
+
+/// *********************************************************************
+/// Define a struct collecting the results of checking a resource
+/// directory without opening a window.
+/// *********************************************************************
+
+pub struct Validation {
+    root: PathBuf,
+    used: HashSet<String>,
+    pub errors: Vec<String>,
+    pub unused: Vec<String>,
+}
+
+impl Validation {
+    pub fn run(root: &Path) -> Validation {
+        let mut v = Validation {root: root.to_path_buf(), used: HashSet::new(), errors: Vec::new(), unused: Vec::new()};
+
+        v.check_waves();
+        v.check_arenas();
+        v.check_lang();
+
+        for sheet in SHEETS {
+            v.check_sheet(sheet);
+        }
+
+        for (path, w, h) in IMAGES {
+            if let Some(size) = v.check_png(path) {
+                if size != (w, h) {
+                    v.error(path, &format!("is {}x{}, expected {}x{}", size.0, size.1, w, h));
+                }
+            }
+        }
+
+        v.check_font(FONT);
+
+        for (_, path) in SFX_FILES {
+            v.check_sound(path);
+        }
+        v.check_sound(MUSIC_FILE);
+
+        v.find_unused();
+        v
+    }
+
+    /// Print the results. Returns false if anything is broken.
+    pub fn print(&self) -> bool {
+        for error in &self.errors {
+            println!("error: {}", error);
+        }
+
+        for file in &self.unused {
+            println!("unused: {}", file);
+        }
+
+        println!("checked {}: {} errors, {} unused files", self.root.display(), self.errors.len(), self.unused.len());
+
+        self.errors.is_empty()
+    }
+
+    fn error(&mut self, path: &str, msg: &str) {
+        self.errors.push(format!("{}: {}", path, msg));
+    }
+
+    /// Read a referenced file, noting it as used.
+    fn read(&mut self, path: &str) -> Option<Vec<u8>> {
+        self.used.insert(path.to_owned());
+
+        match fs::read(self.root.join(path.trim_start_matches('/'))) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                self.error(path, &format!("could not be read ({})", e));
+                None
+            }
+        }
+    }
+
+    fn records(&mut self, path: &str) -> Option<Vec<Record>> {
+        let bytes = self.read(path)?;
+
+        match parse_records(path, &String::from_utf8_lossy(&bytes)) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                self.errors.push(e.to_string());
+                None
+            }
+        }
+    }
+
+    fn check_waves(&mut self) {
+        if let Some(records) = self.records(WAVES_FILE) {
+            if let Err(e) = Waves::from_records(&records) {
+                self.errors.push(e.to_string());
+            }
+        }
+    }
+
+    /// Check the arenas file and the level, background and music of every
+    /// arena in it.
+    fn check_arenas(&mut self) {
+        let arenas = match self.records(ARENAS_FILE).map(|r| arenas_from_records(&r)) {
+            Some(Ok(t)) => t,
+            Some(Err(e)) => return self.errors.push(e.to_string()),
+            None => return,
+        };
+
+        for arena in arenas {
+            self.check_level(&arena.level);
+
+            for image in arena.images() {
+                self.check_png(&image);
+            }
+
+            self.check_sound(&arena.music);
+        }
+    }
+
+    /// Check that the default language has every string the code uses,
+    /// and that the other languages only have strings it has too, so a
+    /// misspelled key doesn't go unnoticed.
+    fn check_lang(&mut self) {
+        let default_path = lang_file(DEFAULT_LANGUAGE);
+
+        let default = match self.records(&default_path).map(|r| Lang::from_records(&r)) {
+            Some(Ok(t)) => t,
+            Some(Err(e)) => return self.errors.push(e.to_string()),
+            None => return,
+        };
+
+        let has = |key: &str| default.iter().any(|(k, _)| k == key);
+
+        for (key, plural) in USED_KEYS {
+            let found = has(key) || (*plural && has(&format!("{}.other", key)));
+
+            if ! found {
+                self.error(&default_path, &format!("is missing the string '{}'", key));
+            }
+        }
+
+        let mut others = Vec::new();
+        list_files(&self.root.join("lang"), &mut others);
+
+        for file in others {
+            let path = resource_path(&self.root, &file);
+
+            if path == default_path {
+                continue;
+            }
+
+            let strings = match self.records(&path).map(|r| Lang::from_records(&r)) {
+                Some(Ok(t)) => t,
+                Some(Err(e)) => {
+                    self.errors.push(e.to_string());
+                    continue;
+                }
+                None => continue,
+            };
+
+            for (key, _) in strings {
+                // Plural forms can differ between languages
+                let base = match key.rsplit_once('.') {
+                    Some((base, form)) if PLURAL_FORMS.contains(&form) => base,
+                    _ => key.as_str(),
+                };
+
+                let known = default.iter().any(|(k, _)| k == base || k.rsplit_once('.').map(|(b, _)| b) == Some(base));
+
+                if ! known {
+                    self.error(&path, &format!("has the string '{}', which {} doesn't", key, default_path));
+                }
+            }
+        }
+    }
+
+    fn check_level(&mut self, path: &str) {
+        if let Some(records) = self.records(path) {
+            if let Err(e) = Tilemap::from_records(&records) {
+                self.errors.push(e.to_string());
+            }
+        }
+    }
+
+    /// Check a sprite sheet's descriptor, its image, that every frame is
+    /// the right size and inside the image, that every frame can be
+    /// mirrored to face both ways, and that every animation is there.
+    fn check_sheet(&mut self, path: &str) {
+        let def = match self.records(path).map(|r| SheetDef::from_records(&r)) {
+            Some(Ok(t)) => t,
+            Some(Err(e)) => return self.errors.push(e.to_string()),
+            None => return,
+        };
+
+        for clip in CLIPS {
+            if def.animation(clip.name()).is_none() {
+                self.error(path, &format!("missing animation '{}'", clip.name()));
+            }
+        }
+
+        let size = self.check_png(&def.image);
+
+        for frame in &def.frames {
+            let rect = frame.rect;
+
+            if rect.w != FRAME_SIZE || rect.h != FRAME_SIZE {
+                self.error(path, &format!("frame '{}' is {}x{}, expected {}x{}", frame.name, rect.w, rect.h, FRAME_SIZE, FRAME_SIZE));
+            }
+
+            if frame.pivot < 0.0 || frame.pivot > rect.w {
+                self.error(path, &format!("frame '{}' has its pivot outside the frame, so it can't be mirrored", frame.name));
+            }
+
+            if let Some((w, h)) = size {
+                if rect.x + rect.w > w as f32 || rect.y + rect.h > h as f32 {
+                    self.error(path, &format!("frame '{}' is outside of {}", frame.name, def.image));
+                }
+            }
+        }
+    }
+
+    /// Check that a PNG exists and looks valid. Returns its size.
+    fn check_png(&mut self, path: &str) -> Option<(u32, u32)> {
+        let bytes = self.read(path)?;
+
+        if bytes.len() < 24 || &bytes[..8] != b"\x89PNG\r\n\x1a\n" || &bytes[12..16] != b"IHDR" {
+            self.error(path, "is not a PNG image");
+            return None;
+        }
+
+        let w = u32::from_be_bytes([bytes[16], bytes[17], bytes[18], bytes[19]]);
+        let h = u32::from_be_bytes([bytes[20], bytes[21], bytes[22], bytes[23]]);
+
+        Some((w, h))
+    }
+
+    /// Check that the font parses with the same glyph loader the game
+    /// draws text with, and that it can draw the score.
+    fn check_font(&mut self, path: &str) {
+        let bytes = match self.read(path) {
+            Some(t) => t,
+            None => return,
+        };
+
+        let font = match FontRef::try_from_slice(&bytes) {
+            Ok(t) => t,
+            Err(e) => return self.error(path, &format!("is not a font that can be loaded ({})", e)),
+        };
+
+        for digit in '0'..='9' {
+            if font.glyph_id(digit).0 == 0 {
+                return self.error(path, &format!("has no glyph for '{}'", digit));
+            }
+        }
+    }
+
+    fn check_sound(&mut self, path: &str) {
+        let bytes = match self.read(path) {
+            Some(t) => t,
+            None => return,
+        };
+
+        let wav = bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WAVE";
+        let ogg = bytes.len() >= 4 && &bytes[..4] == b"OggS";
+
+        if ! wav && ! ogg {
+            self.error(path, "is not a WAV or Ogg file");
+        }
+    }
+
+    fn find_unused(&mut self) {
+        let mut files = Vec::new();
+        list_files(&self.root, &mut files);
+
+        for file in files {
+            let path = resource_path(&self.root, &file);
+
+            if ! self.used.contains(&path) {
+                self.unused.push(path);
+            }
+        }
+
+        self.unused.sort();
+    }
+}
@@ -0,0 +1,118 @@
+use ggez::{Context, GameResult};
+use ggez::conf::{FullscreenType, NumSamples};
+use ggez::graphics::{self, Canvas, Color, DrawParam, FilterMode, Rect};
+use ggez::mint::{Point2, Vector2};
+
+use crate::entity::*;
+use crate::settings::*;
+
+/// *********************************************************************
+/// Create an enumeration of the ways the game can be scaled up to fill
+/// the window. Integer scaling keeps every pixel the same size and
+/// leaves wider bars, fit scaling fills as much of the window as it can.
+/// *********************************************************************
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scaling {
+    Integer,
+    Fit,
+}
+
+impl Scaling {
+    pub fn from_settings() -> Scaling {
+        match fetch_setting_or("scaling", SType::String("integer".to_owned())) {
+            SType::String(t) if t == "fit" => Scaling::Fit,
+            _ => Scaling::Integer,
+        }
+    }
+}
+
+/// Whether the game starts fullscreen.
+pub fn fullscreen_from_settings() -> FullscreenType {
+    match fetch_setting_or("fullscreen", SType::Int(0)) {
+        SType::Int(t) if t != 0 => FullscreenType::Desktop,
+        _ => FullscreenType::Windowed,
+    }
+}
+
+/// *********************************************************************
+/// Define a struct for the viewport. The game is always drawn to a
+/// screen sized canvas, which is then scaled up into the middle of the
+/// window with bars around it. Window positions are in physical pixels,
+/// the same as mouse positions and `resize_event`.
+/// *********************************************************************
+
+pub struct Viewport {
+    pub canvas: Canvas,
+    pub scaling: Scaling,
+    pub fullscreen: bool,
+    pub window: (f32, f32),
+    pub scale: f32,
+    pub offset: Point2<f32>,
+}
+
+impl Viewport {
+    pub fn new(ctx: &mut Context, scaling: Scaling) -> GameResult<Viewport> {
+        let mut canvas = Canvas::new(ctx, SCREEN_WIDTH as u16, SCREEN_HEIGHT as u16, NumSamples::One, graphics::get_window_color_format(ctx))?;
+        canvas.set_filter(FilterMode::Nearest);
+
+        let (w, h) = graphics::drawable_size(ctx);
+        let mut viewport = Viewport {canvas, scaling, fullscreen: fullscreen_from_settings() != FullscreenType::Windowed, window: (w, h), scale: 1.0, offset: Point2 {x: 0.0, y: 0.0}};
+        viewport.resize(w, h);
+
+        Ok(viewport)
+    }
+
+    /// Work out where the game goes in a window of the given size.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.window = (width, height);
+
+        let fit = (width / SCREEN_WIDTH).min(height / SCREEN_HEIGHT);
+
+        // A window too small for even one whole pixel per pixel shrinks the
+        // game instead
+        self.scale = match self.scaling {
+            Scaling::Integer if fit >= 1.0 => fit.floor(),
+            _ => fit,
+        };
+
+        self.offset = Point2 {
+            x: ((width - SCREEN_WIDTH * self.scale) / 2.0).floor(),
+            y: ((height - SCREEN_HEIGHT * self.scale) / 2.0).floor(),
+        };
+    }
+
+    /// Switch between a window and filling the whole display. The new
+    /// size arrives as a resize event.
+    pub fn toggle_fullscreen(&mut self, ctx: &mut Context) -> GameResult {
+        let mode = if self.fullscreen { FullscreenType::Windowed } else { FullscreenType::Desktop };
+        graphics::set_fullscreen(ctx, mode)?;
+        self.fullscreen = ! self.fullscreen;
+
+        Ok(())
+    }
+
+    /// Where a position in the window is on the screen. Positions in the
+    /// bars end up off the screen.
+    pub fn to_screen(&self, x: f32, y: f32) -> Point2<f32> {
+        Point2 {x: (x - self.offset.x) / self.scale, y: (y - self.offset.y) / self.scale}
+    }
+
+    /// Start drawing a frame to the canvas.
+    pub fn begin(&self, ctx: &mut Context) -> GameResult {
+        graphics::set_canvas(ctx, Some(&self.canvas));
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, SCREEN_WIDTH, SCREEN_HEIGHT))
+    }
+
+    /// Draw the finished canvas to the window, between the bars.
+    pub fn present(&self, ctx: &mut Context) -> GameResult {
+        graphics::set_canvas(ctx, None);
+        graphics::set_screen_coordinates(ctx, Rect::new(0.0, 0.0, self.window.0, self.window.1))?;
+        graphics::clear(ctx, Color::BLACK);
+
+        let drawparams = DrawParam::new().dest(self.offset).scale(Vector2 {x: self.scale, y: self.scale});
+        graphics::draw(ctx, &self.canvas, drawparams)?;
+
+        graphics::present(ctx)
+    }
+}
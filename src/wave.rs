@@ -0,0 +1,292 @@
+use ggez::{Context, GameResult};
+
+use oorandom::Rand32;
+
+use crate::arena::*;
+use crate::assets::*;
+use crate::data::*;
+use crate::entity::*;
+
+pub const WAVES_FILE: &str = "/waves.cfg";
+
+/// Ticks per second of the spawn clock.
+const TICKS_PER_SECOND: f32 = 60.0;
+
+/// How long the "wave n" banner stays on screen, in ticks.
+pub const BANNER_TICKS: u32 = 120;
+
+/// *********************************************************************
+/// Define a struct describing the composition of a single wave.
+/// *********************************************************************
+
+#[derive(Clone)]
+pub struct WaveDef {
+    pub spawn: u32,
+    pub batch: u32,
+    pub interval: u32,
+    pub zombie: u32,
+    pub skeleton: u32,
+    pub speed: f32,
+    pub intermission: u32,
+}
+
+impl WaveDef {
+    fn from_record(record: &Record) -> GameResult<WaveDef> {
+        Ok (
+            WaveDef {
+                spawn: record.int("spawn")?,
+                batch: record.int("batch")?,
+                interval: seconds_to_ticks(record.float("interval")?),
+                zombie: record.int_or("zombie", 0)?,
+                skeleton: record.int_or("skeleton", 0)?,
+                speed: record.float_or("speed", 1.0)?,
+                intermission: seconds_to_ticks(record.float("intermission")?),
+            }
+        )
+    }
+
+    /// Pick a monster type using the zombie/skeleton weights, out of the
+    /// monsters in an arena's pool.
+    pub fn pick_monster(&self, rng: &mut Rand32, pool: &[EntityType]) -> EntityType {
+        let zombie = if pool.contains(&EntityType::Zombie) { self.zombie } else { 0 };
+        let skeleton = if pool.contains(&EntityType::Skeleton) { self.skeleton } else { 0 };
+        let total = zombie + skeleton;
+
+        if total == 0 {
+            pool[rng.rand_range(0..pool.len() as u32) as usize].clone()
+        } else if rng.rand_range(0..total) < zombie {
+            EntityType::Zombie
+        } else {
+            EntityType::Skeleton
+        }
+    }
+}
+
+/// *********************************************************************
+/// Create an enumeration of the phases a wave goes through.
+/// *********************************************************************
+
+#[derive(Clone, PartialEq)]
+pub enum WavePhase {
+    Intermission,
+    Spawning,
+    Clearing,
+}
+
+/// *********************************************************************
+/// Define a struct holding the wave table and the progress through it.
+/// *********************************************************************
+
+pub struct Waves {
+    pub table: Vec<WaveDef>,
+    pub endless: WaveDef,
+    pub number: u32,
+    pub phase: WavePhase,
+    pub timer: u32,
+    pub remaining: u32,
+    pub banner: u32,
+}
+
+impl Waves {
+    pub fn new(ctx: &mut Context) -> GameResult<Waves> {
+        let records = load_records(ctx, WAVES_FILE)?;
+        Waves::from_records(&records)
+    }
+
+    /// Load the wave table, falling back to a single repeating wave if it
+    /// can't be loaded.
+    pub fn new_or_default(ctx: &mut Context, report: &mut LoadReport) -> Waves {
+        match Waves::new(ctx) {
+            Ok(t) => t,
+            Err(e) => {
+                report.add(WAVES_FILE, &e);
+
+                let wave = WaveDef {spawn: 4, batch: 2, interval: 240, zombie: 1, skeleton: 1, speed: 1.0, intermission: 180};
+                let endless = WaveDef {spawn: 1, batch: 0, interval: 0, zombie: 0, skeleton: 0, speed: 0.0, intermission: 0};
+                let mut waves = Waves {table: vec![wave], endless, number: 0, phase: WavePhase::Intermission, timer: 0, remaining: 0, banner: 0};
+                waves.reset();
+                waves
+            }
+        }
+    }
+
+    pub fn from_records(records: &[Record]) -> GameResult<Waves> {
+        let mut table = Vec::new();
+        let mut endless = None;
+
+        for record in records {
+            match record.kind.as_str() {
+                "wave" => table.push(WaveDef::from_record(record)?),
+                "endless" => endless = Some(WaveDef::from_record(record)?),
+                _ => return Err(record.error("unknown record type")),
+            }
+        }
+
+        if table.is_empty() {
+            return Err(ggez::GameError::ResourceLoadError(format!("{}: no [wave] records", WAVES_FILE)));
+        }
+
+        let endless = match endless {
+            Some(t) => t,
+            None => WaveDef {spawn: 0, batch: 0, interval: 0, zombie: 0, skeleton: 0, speed: 0.0, intermission: 0},
+        };
+
+        let mut waves = Waves {
+            table,
+            endless,
+            number: 0,
+            phase: WavePhase::Intermission,
+            timer: 0,
+            remaining: 0,
+            banner: 0,
+        };
+        waves.reset();
+
+        Ok(waves)
+    }
+
+    /// Swap in a new wave table, keeping the progress through the waves.
+    pub fn replace_table(&mut self, other: Waves) {
+        self.table = other.table;
+        self.endless = other.endless;
+    }
+
+    /// Go back to before the first wave, with a short pause before it starts.
+    pub fn reset(&mut self) {
+        self.number = 0;
+        self.phase = WavePhase::Intermission;
+        self.timer = self.table[0].intermission;
+        self.remaining = 0;
+        self.banner = 0;
+    }
+
+    /// The definition of wave `number`, counting from 1. Waves past the end
+    /// of the table repeat the last one, grown by the [endless] record once
+    /// for every wave past the end. Every wave spawns at least one monster
+    /// at a time.
+    pub fn def(&self, number: u32) -> WaveDef {
+        let index = (number.max(1) - 1) as usize;
+
+        if index < self.table.len() {
+            let mut def = self.table[index].clone();
+            def.batch = def.batch.max(1);
+            return def;
+        }
+
+        let last = &self.table[self.table.len() - 1];
+        let extra = (index + 1 - self.table.len()) as u32;
+
+        WaveDef {
+            spawn: last.spawn.saturating_add(self.endless.spawn.saturating_mul(extra)),
+            batch: last.batch.saturating_add(self.endless.batch.saturating_mul(extra)).max(1),
+            interval: last.interval.saturating_sub(self.endless.interval.saturating_mul(extra)).max(1),
+            zombie: last.zombie.saturating_add(self.endless.zombie.saturating_mul(extra)),
+            skeleton: last.skeleton.saturating_add(self.endless.skeleton.saturating_mul(extra)),
+            speed: last.speed + self.endless.speed * extra as f32,
+            intermission: last.intermission,
+        }
+    }
+
+    pub fn current(&self) -> WaveDef {
+        self.def(self.number)
+    }
+
+    /// Advance the wave clock by one tick, spawning monsters as needed.
+    pub fn update(&mut self, rng: &mut Rand32, monsters: &mut Vec<Entity>, arena: &Arena) {
+        if self.banner > 0 {
+            self.banner -= 1;
+        }
+
+        match self.phase {
+            WavePhase::Intermission => {
+                if self.timer > 0 {
+                    self.timer -= 1;
+                } else {
+                    self.number += 1;
+                    self.phase = WavePhase::Spawning;
+                    self.remaining = self.current().spawn;
+                    self.banner = BANNER_TICKS;
+                }
+            }
+
+            WavePhase::Spawning => {
+                if self.timer > 0 {
+                    self.timer -= 1;
+                } else {
+                    let wave = self.current();
+                    let batch = rng.rand_range(1..wave.batch + 1).min(self.remaining);
+
+                    spawn_monsters(rng, monsters, batch, &wave, arena);
+                    self.remaining -= batch;
+                    self.timer = wave.interval;
+
+                    if self.remaining == 0 {
+                        self.phase = WavePhase::Clearing;
+                    }
+                }
+            }
+
+            WavePhase::Clearing => {
+                if monsters.is_empty() {
+                    self.phase = WavePhase::Intermission;
+                    self.timer = self.current().intermission;
+                }
+            }
+        }
+    }
+}
+
+fn seconds_to_ticks(seconds: f32) -> u32 {
+    (seconds * TICKS_PER_SECOND).round().max(0.0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WAVES: &str = "
+[wave]
+spawn = 3
+batch = 0
+interval = 2.0
+intermission = 1.0
+
+[endless]
+spawn = 2
+batch = 0
+interval = 0.5
+intermission = 0.0
+";
+
+    fn waves() -> Waves {
+        Waves::from_records(&parse_records("waves.cfg", WAVES).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn batch_is_at_least_one() {
+        let waves = waves();
+
+        assert_eq!(waves.def(1).batch, 1);
+        assert_eq!(waves.def(5).batch, 1);
+    }
+
+    #[test]
+    fn endless_waves_grow() {
+        let waves = waves();
+
+        assert_eq!(waves.def(2).spawn, 5);
+        assert_eq!(waves.def(3).spawn, 7);
+        assert!(waves.def(3).interval < waves.def(2).interval);
+    }
+
+    #[test]
+    fn far_endless_waves_dont_overflow() {
+        let waves = waves();
+
+        for number in [1_000, 100_000_000, u32::MAX] {
+            let def = waves.def(number);
+            assert!(def.interval >= 1);
+            assert!(def.batch >= 1);
+        }
+    }
+}
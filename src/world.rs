@@ -0,0 +1,199 @@
+use ggez::mint::Point2;
+use oorandom::Rand32;
+
+use crate::animation::*;
+use crate::arena::*;
+use crate::entity::*;
+use crate::events::*;
+use crate::score::*;
+use crate::wave::*;
+
+/// *********************************************************************
+/// Define a struct containing the state of the simulation. Nothing in
+/// here needs a Context, so the world can also run headless.
+/// *********************************************************************
+
+pub struct World {
+    pub player: Entity,
+    pub rng: Rand32,
+    pub monsters: Vec<Entity>,
+    pub waves: Waves,
+    pub arena: Arena,
+    pub input: InputState,
+    pub score: usize,
+    pub combo: Combo,
+    pub popups: Vec<Popup>,
+    pub events: Events,
+    pub dead: bool,
+}
+
+impl World {
+    pub fn new(waves: Waves, arena: Arena, seed: u64) -> World {
+        World {
+            player: new_player(arena.start),
+            rng: Rand32::new(seed),
+            monsters: Vec::new(),
+            waves,
+            arena,
+            input: InputState::default(),
+            score: 0,
+            combo: Combo::default(),
+            popups: Vec::new(),
+            events: Events::default(),
+            dead: false,
+        }
+    }
+
+    /// Start a fresh run.
+    pub fn reset(&mut self) {
+        self.monsters.clear();
+        self.player = new_player(self.arena.start);
+        self.score = 0;
+        self.combo.reset();
+        self.popups.clear();
+        self.waves.reset();
+        self.dead = false;
+    }
+
+    /// Move to another arena and start a fresh run there.
+    pub fn set_arena(&mut self, arena: Arena) {
+        self.arena = arena;
+        self.reset();
+    }
+
+    /// The middle of the player, in pixels.
+    pub fn player_center(&self) -> Point2<f32> {
+        Point2 {x: self.player.pos.0 as f32 + 8.0, y: self.player.pos.1 as f32 + 8.0}
+    }
+
+    /// Advance the player by one fixed update step.
+    pub fn update_player(&mut self, controls: bool) {
+        if self.player.falling {
+            self.player.jump += PLAYER_JUMP_TIME;
+        }
+
+        if controls {
+            let was_falling = self.player.falling;
+            handle_player_input(&mut self.player, &mut self.input, &self.arena.map);
+
+            if ! was_falling && self.player.falling {
+                self.events.push(Event::PlayerJumped);
+            }
+
+            if was_falling && ! self.player.falling {
+                self.events.push(Event::PlayerLanded {pos: self.player.pos});
+            }
+        }
+    }
+
+    /// Advance waves, monsters and collisions by one frame.
+    pub fn update(&mut self, spawning: bool) {
+        if spawning {
+            let count = self.monsters.len();
+            let wave = self.waves.number;
+            self.waves.update(&mut self.rng, &mut self.monsters, &self.arena);
+
+            if self.waves.number != wave {
+                self.events.push(Event::WaveStarted {number: self.waves.number});
+            }
+
+            for monster in &self.monsters[count..] {
+                self.events.push(Event::MonsterSpawned {pos: monster.pos});
+            }
+        }
+
+        update_monsters(&mut self.monsters, &self.arena.map);
+
+        let mut alive_monsters = Vec::new();
+
+        for monster in self.monsters.clone() {
+            if is_touching(&self.player, &monster) {
+                if ! self.player.falling {
+                    alive_monsters.push(monster);
+                    self.combo.reset();
+                    if self.player.health > 0 {
+                        self.player.health -= 1;
+                        self.player.anim.request(Clip::Hurt);
+                        self.events.push(Event::PlayerDamaged {pos: self.player.pos});
+                    } else if self.player.health <= 0 && ! self.dead {
+                        self.dead = true;
+                        self.player.anim.request(Clip::Death);
+                        self.events.push(Event::PlayerDied {pos: self.player.pos});
+                    }
+                } else if self.player.falling {
+                    let points = self.combo.stomp(&monster);
+                    self.score += points;
+                    self.popups.push(Popup::new(monster.pos, points));
+                    self.player.jump = 0.0;
+                    self.player.jump_from = self.player.pos.1;
+                    self.events.push(Event::MonsterStomped {tag: monster.tag.clone(), pos: monster.pos, points});
+                    self.events.push(Event::ScoreChanged {score: self.score});
+                }
+            } else {
+                alive_monsters.push(monster);
+            }
+        }
+
+        self.monsters = alive_monsters;
+
+        // The combo only lasts as long as the player stays in the air
+        if ! self.player.falling {
+            self.combo.reset();
+        }
+
+        update_popups(&mut self.popups);
+    }
+}
+
+fn new_player(start: (i16, i16)) -> Entity {
+    let pos = start;
+
+    Entity {
+        tag: EntityType::Player,
+        pos,
+        facing: Direction::Left,
+        anim: Animator::new(Clip::Idle),
+        falling: false,
+        jump: 0.0,
+        jump_from: pos.1,
+        health: 4,
+        ticks: 0,
+        speed: 1.0,
+    }
+}
+
+/// *********************************************************************
+/// Run the world without a window, steering the player with a simple
+/// bot. Returns how the run went.
+/// *********************************************************************
+
+pub fn run_headless(world: &mut World, ticks: u32) -> RunStats {
+    let mut stats = RunStats::default();
+
+    for tick in 0..ticks {
+        if tick % 30 == 0 {
+            world.input.x = match world.rng.rand_range(0..3) {
+                0 => -1.0,
+                1 => 0.0,
+                _ => 1.0,
+            };
+            world.input.jump = ! world.player.falling && world.rng.rand_range(0..2) == 0;
+            if world.input.jump {
+                world.player.jump_from = world.player.pos.1;
+            }
+        }
+
+        world.update_player(true);
+        world.update(true);
+
+        for event in world.events.drain() {
+            stats.handle(&event);
+        }
+
+        if world.dead {
+            break;
+        }
+    }
+
+    stats
+}